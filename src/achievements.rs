@@ -0,0 +1,92 @@
+use crate::events::{Event, Observer};
+
+/// Feats kept across rounds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Achievement {
+    HoleInOne,
+    /// Sinking a shot that bounced off a wall.
+    BankShot,
+    /// Sinking a shot that bounced off `TRICK_SHOT_BANKS` walls.
+    TrickShot,
+}
+
+/// Walls a shot has to bounce off on its way in for a trick shot.
+const TRICK_SHOT_BANKS: u16 = 3;
+
+impl Achievement {
+    pub const ALL: [Achievement; 3] = [
+        Achievement::HoleInOne,
+        Achievement::BankShot,
+        Achievement::TrickShot,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Achievement::HoleInOne => "Hole in one",
+            Achievement::BankShot => "Bank shot",
+            Achievement::TrickShot => "Trick shot",
+        }
+    }
+
+    fn bit(self) -> u8 {
+        1 << self as u8
+    }
+}
+
+/// Unlocks achievements as the events of play come in.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Achievements {
+    /// One bit per `Achievement`, as kept in the save.
+    pub unlocked: u8,
+    /// The bits of `unlocked` that were set on the hole being played.
+    unlocked_on_hole: u8,
+    /// Walls the ball has bounced off since it was last hit.
+    banks: u16,
+}
+
+impl Achievements {
+    pub fn new(unlocked: u8) -> Self {
+        Self {
+            unlocked,
+            ..Self::default()
+        }
+    }
+
+    pub fn has(&self, achievement: Achievement) -> bool {
+        self.unlocked & achievement.bit() != 0
+    }
+
+    /// Whether `achievement` was unlocked for the first time on this hole.
+    pub fn is_new(&self, achievement: Achievement) -> bool {
+        self.unlocked_on_hole & achievement.bit() != 0
+    }
+
+    fn unlock(&mut self, achievement: Achievement) {
+        if !self.has(achievement) {
+            self.unlocked |= achievement.bit();
+            self.unlocked_on_hole |= achievement.bit();
+        }
+    }
+}
+
+impl Observer for Achievements {
+    fn notify(&mut self, event: &Event) {
+        match event {
+            Event::LevelChanged(_) => self.unlocked_on_hole = 0,
+            Event::ShotTaken { .. } => self.banks = 0,
+            Event::WallHit(_) => self.banks += 1,
+            Event::HoleSunk { strokes, .. } => {
+                if *strokes == 1 {
+                    self.unlock(Achievement::HoleInOne);
+                }
+                if self.banks >= 1 {
+                    self.unlock(Achievement::BankShot);
+                }
+                if self.banks >= TRICK_SHOT_BANKS {
+                    self.unlock(Achievement::TrickShot);
+                }
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,26 @@
+use crate::events::{Event, Observer};
+use crate::wasm4::*;
+use crate::MAX_SPEED;
+
+/// Impacts slower than this are silent.
+const MIN_IMPACT: f32 = 0.05;
+
+/// Plays the sounds of the game.
+pub struct Audio;
+
+impl Observer for Audio {
+    fn notify(&mut self, event: &Event) {
+        match event {
+            Event::ShotTaken { .. } => tone(220, 2, 20, TONE_NOISE),
+            Event::WallHit(impact) if impact.speed > MIN_IMPACT => tone(
+                (impact.speed / MAX_SPEED * 100.0 + 450.0) as u32,
+                1,
+                (impact.speed / MAX_SPEED * 50.0 + 50.0) as u32,
+                TONE_TRIANGLE,
+            ),
+            Event::HoleSunk { .. } => tone(600, 1, 100, TONE_PULSE1),
+            Event::HazardEntered(_) => tone(150, 10, 60, TONE_PULSE2),
+            _ => {}
+        }
+    }
+}
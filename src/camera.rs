@@ -0,0 +1,148 @@
+use std::ops::Range;
+
+
+use crate::game::{Level, Position};
+use crate::wasm4::SCREEN_SIZE;
+use crate::{BALL_SIZE, OVERVIEW_SCALE, SCALE};
+
+/// Share of the way to its target the camera moves each frame.
+const FOLLOW: f32 = 0.15;
+/// Frames of travel the camera looks ahead of a rolling ball.
+const LOOK_AHEAD: f32 = 6.0;
+/// Tiles the camera moves per frame while panning, at `SCALE`.
+const PAN_SPEED: f32 = 0.5;
+/// Share of the way to the overview or play scale the zoom moves each frame.
+const ZOOM: f32 = 0.2;
+/// Pixels left free around the hole in the overview.
+const OVERVIEW_MARGIN: f32 = 4.0;
+
+/// The part of the level on screen: the point in the middle of the screen and
+/// how many pixels a tile takes up.
+#[derive(Debug, Clone, Copy)]
+pub struct Camera {
+    pub center: Position,
+    /// Pixels per tile, fractional while zooming and in the overview of big holes.
+    pub scale: f32,
+    /// Whether the player moved the camera away from the ball to look around.
+    pub panning: bool,
+    /// Whether to zoom out to show the whole hole.
+    pub overview: bool,
+}
+
+impl Camera {
+    pub fn new() -> Self {
+        Self {
+            center: Position { x: 0.0, y: 0.0 },
+            scale: SCALE as f32,
+            panning: false,
+            overview: false,
+        }
+    }
+
+    /// Jumps straight to the ball at the play scale, as when a hole starts.
+    pub fn snap(&mut self, level: &Level, ball: Position) {
+        self.center = ball;
+        self.scale = SCALE as f32;
+        self.panning = false;
+        self.overview = false;
+        self.clamp(level);
+    }
+
+    /// Eases towards the ball and a little ahead of it, unless panning, and
+    /// zooms towards the overview or play scale.
+    pub fn update(&mut self, level: &Level, ball: Position, velocity: Position) {
+        let target = if self.overview {
+            fit_scale(level)
+        } else {
+            SCALE as f32
+        };
+        self.scale += (target - self.scale) * ZOOM;
+        if (target - self.scale).abs() < 0.01 {
+            self.scale = target;
+        }
+
+        if !self.panning {
+            let target_x = ball.x + velocity.x * LOOK_AHEAD;
+            let target_y = ball.y + velocity.y * LOOK_AHEAD;
+            self.center.x += (target_x - self.center.x) * FOLLOW;
+            self.center.y += (target_y - self.center.y) * FOLLOW;
+        }
+        self.clamp(level);
+    }
+
+    /// Moves the camera by `x`, `y` times the pan speed, away from the ball.
+    pub fn pan(&mut self, level: &Level, x: f32, y: f32) {
+        let speed = PAN_SPEED * SCALE as f32 / self.scale;
+        self.panning = true;
+        self.center.x += x * speed;
+        self.center.y += y * speed;
+        self.clamp(level);
+    }
+
+    /// Keeps the view inside the level, or centred on it if it fits on screen.
+    fn clamp(&mut self, level: &Level) {
+        let half = SCREEN_SIZE as f32 / 2.0 / self.scale;
+        let clamp_axis = |center: f32, min: f32, max: f32| {
+            if max - min <= half * 2.0 {
+                (min + max) / 2.0
+            } else {
+                center.max(min + half).min(max - half)
+            }
+        };
+        // Row `r` from the top covers `rows - r` to `rows - r + 1`.
+        self.center.x = clamp_axis(self.center.x, 0.0, level.width as f32);
+        self.center.y = clamp_axis(self.center.y, 1.0, level.rows() as f32 + 1.0);
+    }
+
+    /// Whether tiles are drawn pixel for pixel from a sprite atlas, rather than
+    /// as blocks of colour.
+    pub fn uses_sprites(&self) -> bool {
+        self.scale == SCALE as f32 || self.scale == OVERVIEW_SCALE as f32
+    }
+
+    /// Screen position of a point in the level.
+    pub fn screen_position(&self, position: Position) -> (i32, i32) {
+        (
+            SCREEN_SIZE as i32 / 2 + ((position.x - self.center.x) * self.scale).floor() as i32,
+            SCREEN_SIZE as i32 / 2 - ((position.y - self.center.y) * self.scale).floor() as i32,
+        )
+    }
+
+    /// Screen position of the top left corner of the tile in `column` and `row`,
+    /// counting rows from the top.
+    pub fn tile_screen_position(&self, level: &Level, column: u16, row: u16) -> (i32, i32) {
+        let y = (level.rows() as i32 - row as i32) as f32;
+        let offset = (BALL_SIZE * SCALE as u32 / 2 + 1) as f32 * self.scale / SCALE as f32;
+        (
+            SCREEN_SIZE as i32 / 2 + ((column as f32 - self.center.x) * self.scale).floor() as i32,
+            SCREEN_SIZE as i32 / 2 - ((y - self.center.y) * self.scale + offset).floor() as i32,
+        )
+    }
+
+    /// Columns and rows, counted from the top, of the tiles that are at least
+    /// partly on screen.
+    pub fn visible_tiles(&self, level: &Level) -> (Range<u16>, Range<u16>) {
+        // Half the screen in tiles, with a tile to spare for rounding.
+        let half = SCREEN_SIZE as f32 / 2.0 / self.scale + 1.0;
+        let rows = level.rows() as f32;
+        let clamp = |value: f32, max: u16| value.clamp(0.0, max as f32) as u16;
+        (
+            clamp(self.center.x - half, level.width)..clamp(self.center.x + half + 1.0, level.width),
+            clamp(rows - self.center.y - half, level.rows())
+                ..clamp(rows - self.center.y + half + 1.0, level.rows()),
+        )
+    }
+}
+
+/// The scale that fits the whole of `level` on screen. Whole numbers are kept
+/// where they fit, so tiles stay crisp, and holes that fit at the play scale
+/// aren't zoomed in on.
+fn fit_scale(level: &Level) -> f32 {
+    let size = SCREEN_SIZE as f32 - OVERVIEW_MARGIN * 2.0;
+    let scale = (size / level.width as f32).min(size / level.rows() as f32);
+    if scale >= 1.0 {
+        scale.floor().min(SCALE as f32)
+    } else {
+        scale
+    }
+}
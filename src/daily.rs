@@ -0,0 +1,140 @@
+use crate::levelgen::Rng;
+use crate::theme::{self, Role};
+use crate::wasm4::*;
+
+/// Difficulty every daily hole is generated with.
+pub const DIFFICULTY: u8 = 2;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Date {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+}
+
+impl Date {
+    fn is_leap_year(&self) -> bool {
+        self.year.is_multiple_of(4)
+            && (!self.year.is_multiple_of(100) || self.year.is_multiple_of(400))
+    }
+
+    pub fn days_in_month(&self) -> u8 {
+        match self.month {
+            2 if self.is_leap_year() => 29,
+            2 => 28,
+            4 | 6 | 9 | 11 => 30,
+            _ => 31,
+        }
+    }
+
+    /// Packs the date as `yyyymmdd`, which is also how it is stored on disk.
+    pub fn to_u32(self) -> u32 {
+        self.year as u32 * 10000 + self.month as u32 * 100 + self.day as u32
+    }
+
+    pub fn from_u32(packed: u32) -> Option<Self> {
+        let date = Date {
+            year: (packed / 10000) as u16,
+            month: (packed / 100 % 100) as u8,
+            day: (packed % 100) as u8,
+        };
+        let valid = (1..=12).contains(&date.month) && (1..=date.days_in_month()).contains(&date.day);
+        valid.then_some(date)
+    }
+
+    /// Seed of the date's hole, the same for everyone who enters it.
+    pub fn seed(&self) -> u32 {
+        Rng::new(self.to_u32()).next_u32()
+    }
+}
+
+/// Whether the hole of `date` can still be played, given the date of the last
+/// one played as `Date::to_u32`. Only that one's result is kept, so earlier
+/// dates are refused too, or any day could be tried again after another one.
+pub fn is_playable(date: Date, last_played: u32) -> bool {
+    date.to_u32() > last_played
+}
+
+/// Screen for typing in today's date, since WASM-4 has no clock.
+pub struct DateEntry {
+    pub date: Date,
+    /// Which of year, month and day is being edited.
+    field: u8,
+    previous_gamepad: u8,
+}
+
+impl Default for DateEntry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DateEntry {
+    pub const fn new() -> Self {
+        Self {
+            date: Date {
+                year: 2024,
+                month: 1,
+                day: 1,
+            },
+            field: 2,
+            previous_gamepad: 0,
+        }
+    }
+
+    pub fn open(&mut self, date: Option<Date>) {
+        if let Some(date) = date {
+            self.date = date;
+        }
+        self.field = 2;
+        self.previous_gamepad = unsafe { *GAMEPAD1 };
+    }
+
+    /// Draws the date and returns `Some(true)` once it is confirmed with X or
+    /// `Some(false)` if Z backs out.
+    pub fn update(&mut self, x: i32, y: i32) -> Option<bool> {
+        let gamepad = unsafe { *GAMEPAD1 };
+        let pressed = gamepad & !self.previous_gamepad;
+        let released = self.previous_gamepad & !gamepad;
+        self.previous_gamepad = gamepad;
+
+        if pressed & BUTTON_LEFT != 0 {
+            self.field = self.field.saturating_sub(1);
+        } else if pressed & BUTTON_RIGHT != 0 {
+            self.field = (self.field + 1).min(2);
+        } else if pressed & (BUTTON_UP | BUTTON_DOWN) != 0 {
+            let up = pressed & BUTTON_UP != 0;
+            let date = &mut self.date;
+            match self.field {
+                0 if up => date.year = (date.year + 1).min(9999),
+                0 => date.year = date.year.saturating_sub(1).max(2000),
+                1 if up => date.month = date.month % 12 + 1,
+                1 => date.month = (date.month + 10) % 12 + 1,
+                _ => {
+                    let days = date.days_in_month();
+                    date.day = if up { date.day % days + 1 } else { (date.day + days - 2) % days + 1 };
+                }
+            }
+            self.date.day = self.date.day.min(self.date.days_in_month());
+        }
+
+        theme::set_color(Role::Title);
+        text(
+            format!("{:04}-{:02}-{:02}", self.date.year, self.date.month, self.date.day),
+            x,
+            y,
+        );
+        // Underline the field being edited.
+        let (offset, length) = [(0, 32), (40, 16), (64, 16)][self.field as usize];
+        theme::set_color(Role::Highlight);
+        hline(x + offset, y + 9, length);
+
+        if released & BUTTON_1 != 0 {
+            return Some(true);
+        }
+        if released & BUTTON_2 != 0 {
+            return Some(false);
+        }
+        None
+    }
+}
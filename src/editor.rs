@@ -0,0 +1,331 @@
+use crate::game::{Level, Tile};
+use crate::levels::map;
+use crate::menu::Menu;
+use crate::save::{Save, CUSTOM_LEVEL_SIZE};
+use crate::sprites::{self, Sprite};
+use crate::theme::{self, Role};
+use crate::wasm4::*;
+use crate::SCALE;
+
+const MIN_SIZE: u16 = 4;
+/// Kept small enough that a level and the search for its cup fit in the heap.
+const MAX_WIDTH: u16 = 40;
+const MAX_HEIGHT: u16 = 40;
+/// Frames an arrow is held before the cursor starts repeating, and how often it repeats.
+const REPEAT_DELAY: u8 = 15;
+const REPEAT_RATE: u8 = 4;
+/// The walled in room a new level starts as.
+const TEMPLATE: &str = "
+l------------------r
+|                  |
+|                  |
+|                  |
+|                  |
+|                  |
+|                  |
+|   p          x   |
+|                  |
+|                  |
+|                  |
+|                  |
+|                  |
+L------------------R";
+
+pub enum Action {
+    None,
+    TestPlay,
+    Exit,
+}
+
+pub struct Editor {
+    pub level: Level,
+    cursor_x: u16,
+    cursor_y: u16,
+    brush: Tile,
+    menu_open: bool,
+    menu: Menu,
+    message: &'static str,
+    held: u8,
+    previous_gamepad: u8,
+}
+
+impl Editor {
+    pub const fn new() -> Self {
+        Self {
+            level: Level {
+                tiles: Vec::new(),
+                width: 1,
+            },
+            cursor_x: 0,
+            cursor_y: 0,
+            brush: Tile::VerticalWall,
+            menu_open: false,
+            menu: Menu::new(),
+            message: "",
+            held: 0,
+            previous_gamepad: 0,
+        }
+    }
+
+    /// Loads the level saved on disk, or a walled in room if there is none or
+    /// it's bigger than the editor allows.
+    pub fn open(&mut self, save: &Save) {
+        self.level = decode(&save.custom_level)
+            .filter(|level| level.width <= MAX_WIDTH && level.rows() <= MAX_HEIGHT)
+            .unwrap_or_else(template);
+        self.cursor_x = self.level.width / 2;
+        self.cursor_y = self.level.rows() / 2;
+        self.menu_open = false;
+        self.message = "Z: menu";
+        self.previous_gamepad = unsafe { *GAMEPAD1 };
+    }
+
+    pub fn update(&mut self, save: &mut Save) -> Action {
+        self.draw();
+
+        if self.menu_open {
+            return self.update_menu(save);
+        }
+
+        let gamepad = unsafe { *GAMEPAD1 };
+        let pressed = gamepad & !self.previous_gamepad;
+        let released = self.previous_gamepad & !gamepad;
+        self.previous_gamepad = gamepad;
+
+        let arrows = BUTTON_LEFT | BUTTON_RIGHT | BUTTON_UP | BUTTON_DOWN;
+        let moves = if pressed & arrows != 0 {
+            self.held = 0;
+            pressed
+        } else if gamepad & arrows != 0 {
+            self.held = self.held.saturating_add(1);
+            if self.held >= REPEAT_DELAY && (self.held - REPEAT_DELAY).is_multiple_of(REPEAT_RATE) {
+                gamepad
+            } else {
+                0
+            }
+        } else {
+            0
+        };
+        if moves & BUTTON_LEFT != 0 {
+            self.cursor_x = self.cursor_x.saturating_sub(1);
+        }
+        if moves & BUTTON_RIGHT != 0 {
+            self.cursor_x = (self.cursor_x + 1).min(self.level.width - 1);
+        }
+        if moves & BUTTON_UP != 0 {
+            self.cursor_y = self.cursor_y.saturating_sub(1);
+        }
+        if moves & BUTTON_DOWN != 0 {
+            self.cursor_y = (self.cursor_y + 1).min(self.level.rows() - 1);
+        }
+
+        if gamepad & BUTTON_1 != 0 {
+            self.paint(self.cursor_x, self.cursor_y, self.brush);
+        }
+
+        let mouse = unsafe { *MOUSE_BUTTONS };
+        if mouse & (MOUSE_LEFT | MOUSE_RIGHT) != 0 {
+            if let Some((x, y)) = self.mouse_tile() {
+                let tile = if mouse & MOUSE_LEFT != 0 { self.brush } else { Tile::Empty };
+                self.paint(x, y, tile);
+            }
+        }
+
+        if released & BUTTON_2 != 0 {
+            self.menu_open = true;
+            self.menu.open();
+        }
+        Action::None
+    }
+
+    fn update_menu(&mut self, save: &mut Save) -> Action {
+        theme::set_draw_colors(Some(Role::Background), Some(Role::Text));
+        rect(16, 30, 128, 94);
+        let brush = format!("Brush: {}", self.brush.name());
+        let items = [
+            brush.as_str(),
+            "Width +",
+            "Width -",
+            "Height +",
+            "Height -",
+            "Test play",
+            "Save",
+            "Close menu",
+            "Exit editor",
+        ];
+        let width = self.level.width;
+        let height = self.level.rows();
+        match self.menu.update(&items, 24, 34) {
+            Some(0) => {
+                let index = Tile::ALL.iter().position(|tile| *tile == self.brush).unwrap();
+                self.brush = Tile::ALL[(index + 1) % Tile::ALL.len()];
+            }
+            Some(1) => self.resize(width + 1, height),
+            Some(2) => self.resize(width - 1, height),
+            Some(3) => self.resize(width, height + 1),
+            Some(4) => self.resize(width, height - 1),
+            Some(5) => {
+                if !self.level.tiles.contains(&Tile::Player) {
+                    self.message = "Place a tee first";
+                } else if !self.level.tiles.contains(&Tile::Goal) {
+                    self.message = "Place a cup first";
+                } else if self.level.path_length().is_none() {
+                    self.message = "Cup can't be reached";
+                } else {
+                    self.menu_open = false;
+                    return Action::TestPlay;
+                }
+                self.menu_open = false;
+            }
+            Some(6) => {
+                match encode(&self.level) {
+                    Some(bytes) => {
+                        save.custom_level = bytes;
+                        save.store();
+                        // Also log it, ready to paste into `levels.rs`.
+                        trace(format!("Level:{}", self.level));
+                        self.message = "Saved";
+                    }
+                    None => self.message = "Too big to save",
+                }
+                self.menu_open = false;
+            }
+            Some(7) => self.menu_open = false,
+            Some(8) => return Action::Exit,
+            _ => {}
+        }
+        Action::None
+    }
+
+    /// Places `tile`, moving the tee or the cup if one is already placed.
+    fn paint(&mut self, x: u16, y: u16, tile: Tile) {
+        if tile == Tile::Player || tile == Tile::Goal {
+            for existing in self.level.tiles.iter_mut().filter(|t| **t == tile) {
+                *existing = Tile::Empty;
+            }
+        }
+        self.level.tiles[(y * self.level.width + x) as usize] = tile;
+    }
+
+    fn resize(&mut self, width: u16, height: u16) {
+        let width = width.clamp(MIN_SIZE, MAX_WIDTH);
+        let height = height.clamp(MIN_SIZE, MAX_HEIGHT);
+        let old = &self.level;
+        let mut tiles = Vec::with_capacity((width * height) as usize);
+        for y in 0..height {
+            for x in 0..width {
+                let tile = if x < old.width && y < old.rows() {
+                    old.tiles[(y * old.width + x) as usize]
+                } else {
+                    Tile::Empty
+                };
+                tiles.push(tile);
+            }
+        }
+        self.level = Level { tiles, width };
+        self.cursor_x = self.cursor_x.min(width - 1);
+        self.cursor_y = self.cursor_y.min(height - 1);
+    }
+
+    /// Screen position of the top left corner of a tile, with the cursor in the middle.
+    fn tile_to_screen(&self, x: u16, y: u16) -> (i32, i32) {
+        let scale = SCALE as i32;
+        (
+            SCREEN_SIZE as i32 / 2 + (x as i32 - self.cursor_x as i32) * scale - scale / 2,
+            SCREEN_SIZE as i32 / 2 + (y as i32 - self.cursor_y as i32) * scale - scale / 2,
+        )
+    }
+
+    fn mouse_tile(&self) -> Option<(u16, u16)> {
+        let scale = SCALE as i32;
+        let (left, top) = self.tile_to_screen(0, 0);
+        let x = (unsafe { *MOUSE_X } as i32 - left).div_euclid(scale);
+        let y = (unsafe { *MOUSE_Y } as i32 - top).div_euclid(scale);
+        let inside = x >= 0
+            && y >= 0
+            && x < self.level.width as i32
+            && y < self.level.rows() as i32;
+        inside.then_some((x as u16, y as u16))
+    }
+
+    fn draw(&self) {
+        let scale = SCALE as u32;
+        let (left, top) = self.tile_to_screen(0, 0);
+        theme::set_draw_colors(None, Some(Role::Highlight));
+        rect(
+            left - 1,
+            top - 1,
+            self.level.width as u32 * scale + 2,
+            self.level.rows() as u32 * scale + 2,
+        );
+
+        // Only the tiles around the cursor are on screen, with one to spare for
+        // the half tile it's offset by.
+        let half = (SCREEN_SIZE / 2 / scale) as u16 + 1;
+        let (width, height) = (self.level.width, self.level.rows());
+        let columns = self.cursor_x.saturating_sub(half)..(self.cursor_x + half + 1).min(width);
+        let rows = self.cursor_y.saturating_sub(half)..(self.cursor_y + half + 1).min(height);
+        for row in rows {
+            for column in columns.clone() {
+                let tile = self.level.tiles[(row * width + column) as usize];
+                let (x, y) = self.tile_to_screen(column, row);
+                if tile == Tile::Player {
+                    sprites::draw(Sprite::Ball, x, y, scale, 0);
+                } else {
+                    tile.draw(x, y, scale);
+                }
+            }
+        }
+
+        let (x, y) = self.tile_to_screen(self.cursor_x, self.cursor_y);
+        theme::set_draw_colors(None, Some(Role::Title));
+        rect(x - 1, y - 1, scale + 2, scale + 2);
+
+        theme::set_draw_colors(Some(Role::Highlight), Some(Role::Title));
+        text(
+            format!("{}x{}", self.level.width, self.level.rows()),
+            0,
+            0,
+        );
+        text(format!("Brush: {}", self.brush.name()), 0, 144);
+        text(self.message, 0, 152);
+    }
+}
+
+fn template() -> Level {
+    decode(map!(TEMPLATE)).unwrap()
+}
+
+/// Run length encodes a level as its width and height followed by one byte per
+/// run: the tile's index in `Tile::ALL` in the low nibble and the run length
+/// minus one in the high nibble.
+pub fn encode(level: &Level) -> Option<Vec<u8>> {
+    let mut bytes = vec![level.width as u8, level.rows() as u8];
+    for run in level.tiles.chunk_by(|a, b| a == b) {
+        let index = Tile::ALL.iter().position(|tile| *tile == run[0])? as u8;
+        for part in run.chunks(16) {
+            bytes.push(index | ((part.len() as u8 - 1) << 4));
+        }
+    }
+    (bytes.len() <= CUSTOM_LEVEL_SIZE).then_some(bytes)
+}
+
+pub fn decode(bytes: &[u8]) -> Option<Level> {
+    let (&width, rest) = bytes.split_first()?;
+    let (&height, runs) = rest.split_first()?;
+    let size = width as usize * height as usize;
+    if size == 0 {
+        return None;
+    }
+    let mut tiles = Vec::with_capacity(size);
+    for run in runs {
+        let tile = *Tile::ALL.get((run & 0xf) as usize)?;
+        for _ in 0..=(run >> 4) {
+            tiles.push(tile);
+        }
+    }
+    (tiles.len() == size).then_some(Level {
+        tiles,
+        width: width as u16,
+    })
+}
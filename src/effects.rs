@@ -0,0 +1,215 @@
+use std::f32::consts::{FRAC_1_SQRT_2, PI};
+
+use crate::camera::Camera;
+use crate::events::{Event, Observer};
+use crate::game::Position;
+use crate::theme::{self, Role};
+use crate::wasm4::*;
+use crate::{BALL_SIZE, MAX_SPEED};
+
+/// Positions the trail behind a rolling ball is made of.
+const TRAIL_LENGTH: usize = 12;
+const SPARK_FRAMES: u8 = 10;
+/// Speed, in tiles per frame, sparks fly off a wall at when hit at full speed.
+const SPARK_SPEED: f32 = 0.3;
+/// Directions sparks fly off in, taken in turn.
+const SPARK_DIRECTIONS: [(f32, f32); 8] = [
+    (1.0, 0.0),
+    (-FRAC_1_SQRT_2, FRAC_1_SQRT_2),
+    (0.0, -1.0),
+    (FRAC_1_SQRT_2, FRAC_1_SQRT_2),
+    (-1.0, 0.0),
+    (FRAC_1_SQRT_2, -FRAC_1_SQRT_2),
+    (0.0, 1.0),
+    (-FRAC_1_SQRT_2, -FRAC_1_SQRT_2),
+];
+/// Impacts slower than this don't throw sparks.
+const SPARK_MIN_IMPACT: f32 = 0.05;
+/// Frames the ball takes to drop into the cup.
+const SINK_FRAMES: u8 = 24;
+
+struct Spark {
+    position: Position,
+    velocity: Position,
+    age: u8,
+}
+
+struct Sink {
+    from: Position,
+    cup: Position,
+    frames_left: u8,
+}
+
+/// Purely visual feedback on the ball's movement, fed by its position each frame
+/// and by gameplay events. Nothing here changes where the ball goes.
+pub struct Effects {
+    pub enabled: bool,
+    /// Recent positions of the ball, oldest first.
+    trail: Vec<Position>,
+    sparks: Vec<Spark>,
+    /// Tiles the ball has rolled, for turning its highlight.
+    rolled: f32,
+    sink: Option<Sink>,
+}
+
+impl Default for Effects {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Effects {
+    pub fn new() -> Self {
+        Self {
+            enabled: true,
+            trail: Vec::with_capacity(TRAIL_LENGTH),
+            sparks: Vec::new(),
+            rolled: 0.0,
+            sink: None,
+        }
+    }
+
+    /// Forgets everything, as when the ball is put on a new tee.
+    pub fn clear(&mut self) {
+        self.trail.clear();
+        self.sparks.clear();
+        self.sink = None;
+    }
+
+    /// Whether the ball is still dropping into the cup.
+    pub fn sinking(&self) -> bool {
+        self.sink.is_some()
+    }
+
+    pub fn update(&mut self, position: Position, velocity: Position) {
+        if !self.enabled {
+            self.clear();
+            return;
+        }
+
+        for spark in &mut self.sparks {
+            spark.position.x += spark.velocity.x;
+            spark.position.y += spark.velocity.y;
+            spark.age += 1;
+        }
+        self.sparks.retain(|spark| spark.age < SPARK_FRAMES);
+
+        if let Some(sink) = &mut self.sink {
+            sink.frames_left -= 1;
+            if sink.frames_left == 0 {
+                self.sink = None;
+            }
+            return;
+        }
+
+        let speed = (velocity.x * velocity.x + velocity.y * velocity.y).sqrt();
+        self.rolled += speed;
+        if speed > 0.0 {
+            if self.trail.len() == TRAIL_LENGTH {
+                self.trail.remove(0);
+            }
+            self.trail.push(position);
+        } else if !self.trail.is_empty() {
+            // Let the trail catch up with a ball at rest.
+            self.trail.remove(0);
+        }
+    }
+
+    /// Draws the trail and sparks, which go under the ball.
+    pub fn draw_behind(&self, camera: &Camera) {
+        for (i, point) in self.trail.iter().enumerate() {
+            // Older points fade to the wall colour and thin out.
+            let old = i < self.trail.len() / 2;
+            if old && i % 2 == 1 {
+                continue;
+            }
+            let (x, y) = camera.screen_position(*point);
+            let role = if old { Role::Wall } else { Role::Highlight };
+            theme::set_draw_colors(Some(role), Some(role));
+            rect(x, y, 1, 1);
+        }
+
+        for spark in &self.sparks {
+            let (x, y) = camera.screen_position(spark.position);
+            let role = if spark.age < SPARK_FRAMES / 2 {
+                Role::Title
+            } else {
+                Role::Highlight
+            };
+            theme::set_draw_colors(Some(role), Some(role));
+            rect(x, y, 1, 1);
+        }
+    }
+
+    /// Draws what goes on top of the ball: the highlight that turns as it
+    /// rolls, or the ball itself while it drops into the cup.
+    pub fn draw_ball(&self, camera: &Camera, position: Position, velocity: Position) {
+        let size = BALL_SIZE as f32 * camera.scale;
+
+        if let Some(sink) = &self.sink {
+            let left = sink.frames_left as f32 / SINK_FRAMES as f32;
+            let ball = Position {
+                x: sink.cup.x + (sink.from.x - sink.cup.x) * left,
+                y: sink.cup.y + (sink.from.y - sink.cup.y) * left,
+            };
+            let (x, y) = camera.screen_position(ball);
+            let size = (size * left) as u32;
+            theme::set_draw_colors(Some(Role::Ball), Some(Role::Ball));
+            if size > 0 {
+                oval(x - size as i32 / 2, y - size as i32 / 2, size, size);
+            }
+            return;
+        }
+
+        let speed = (velocity.x * velocity.x + velocity.y * velocity.y).sqrt();
+        if !self.enabled || speed == 0.0 || size < 3.0 {
+            return;
+        }
+        // The highlight runs across the ball in the direction of travel once
+        // per turn.
+        let turn = self.rolled / (PI * BALL_SIZE as f32);
+        let offset = (turn - turn.floor() - 0.5) * size;
+        let (x, y) = camera.screen_position(Position {
+            x: position.x + velocity.x / speed * offset / camera.scale,
+            y: position.y + velocity.y / speed * offset / camera.scale,
+        });
+        theme::set_draw_colors(Some(Role::Highlight), Some(Role::Highlight));
+        rect(x, y, 1, 1);
+    }
+}
+
+impl Observer for Effects {
+    fn notify(&mut self, event: &Event) {
+        match event {
+            // The ball jumps to another spot, so the trail would cut across.
+            Event::LevelChanged(_) | Event::HazardEntered(_) => self.clear(),
+            _ if !self.enabled => {}
+            Event::WallHit(impact) if impact.speed > SPARK_MIN_IMPACT => {
+                let count = 2 + (impact.speed / MAX_SPEED * 4.0) as usize;
+                let speed = impact.speed / MAX_SPEED * SPARK_SPEED;
+                // Start somewhere different each time so impacts don't all look alike.
+                let first = self.rolled as usize;
+                for i in 0..count {
+                    let (x, y) = SPARK_DIRECTIONS[(first + i) % SPARK_DIRECTIONS.len()];
+                    self.sparks.push(Spark {
+                        position: impact.position,
+                        velocity: Position {
+                            x: x * speed,
+                            y: y * speed,
+                        },
+                        age: 0,
+                    });
+                }
+            }
+            Event::HoleSunk { ball, cup, .. } => {
+                self.trail.clear();
+                self.sink = Some(Sink {
+                    from: *ball,
+                    cup: *cup,
+                    frames_left: SINK_FRAMES,
+                });
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,24 @@
+use crate::game::{Impact, Position};
+
+/// Something that happened in play, queued by the game and handed to every
+/// `Observer` once per frame.
+#[derive(Debug, Clone, Copy)]
+pub enum Event {
+    /// The ball was hit with a drag of `x`, `y` pixels.
+    ShotTaken { x: i16, y: i16 },
+    WallHit(Impact),
+    /// The ball came to rest after rolling.
+    BallStopped(Position),
+    /// The ball rolled off the course at this spot, and is put back where it
+    /// was hit from.
+    HazardEntered(Position),
+    /// The ball dropped into the cup at `cup`, from `ball`.
+    HoleSunk { strokes: u16, ball: Position, cup: Position },
+    /// The ball was put on the tee of this hole.
+    LevelChanged(u16),
+}
+
+/// Reacts to gameplay events, without being able to change the play itself.
+pub trait Observer {
+    fn notify(&mut self, event: &Event);
+}
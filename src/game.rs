@@ -1,16 +1,88 @@
+use std::fmt::{self, Write};
 use std::str::FromStr;
 
-use libm::{sqrtf, fmodf};
+use libm::atan2f;
 
-use super::{levels, BALL_SIZE, DECCELERATION, MAX_SPEED};
-use crate::{wasm4::*, SCALE};
+use super::{levels, BALL_SIZE, DECCELERATION, MAX_SPEED, PUSH_FORCE};
+use crate::achievements::{Achievement, Achievements};
+use crate::audio::Audio;
+use crate::daily::{self, Date};
+use crate::effects::Effects;
+use crate::events::{Event, Observer};
+use crate::ghost::Ghost;
+use crate::levelgen;
+use crate::replay::{Playback, Replay, Shot, MAX_DRAG};
+use crate::save::{HoleRecord, Save, MAX_GHOST_SHOTS};
+use crate::camera::Camera;
+use crate::sprites::{self, Sprite};
+use crate::stats::Stats;
+use crate::theme::{self, Role, Theme};
+use crate::transition::{Kind, Transition};
+use crate::wasm4::*;
+use crate::SCALE;
 
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum State {
     Menu,
+    HoleSelect,
+    DailyEntry,
+    Editor,
+    /// The card naming the hole, before it can be played.
+    HoleIntro,
     Playing,
+    Paused,
+    /// The card with the strokes taken, after the ball is sunk.
+    HoleOutro,
     GameOver,
 }
 
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Difficulty {
+    Easy,
+    Normal,
+    Tournament,
+}
+
+impl Difficulty {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "Easy",
+            Difficulty::Normal => "Normal",
+            Difficulty::Tournament => "Tournament",
+        }
+    }
+
+    /// Shots that can be taken back per round.
+    pub fn mulligans(&self) -> u8 {
+        match self {
+            Difficulty::Easy => 5,
+            Difficulty::Normal => 2,
+            Difficulty::Tournament => 0,
+        }
+    }
+
+    /// Whether the aim preview may be turned on.
+    pub fn allows_aim_assist(&self) -> bool {
+        *self != Difficulty::Tournament
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            Difficulty::Easy => Difficulty::Normal,
+            Difficulty::Normal => Difficulty::Tournament,
+            Difficulty::Tournament => Difficulty::Easy,
+        }
+    }
+
+    pub fn previous(&self) -> Self {
+        match self {
+            Difficulty::Easy => Difficulty::Tournament,
+            Difficulty::Normal => Difficulty::Easy,
+            Difficulty::Tournament => Difficulty::Normal,
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum Tile {
     VerticalWall,
@@ -24,8 +96,27 @@ pub enum Tile {
     Goal,
 }
 
+/// Why a level's text couldn't be parsed.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ParseError {
+    Empty,
+    UnknownTile(char),
+    /// A row, counted from the top, that isn't as wide as the first one.
+    UnevenRow(usize),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::Empty => write!(f, "no rows"),
+            ParseError::UnknownTile(c) => write!(f, "unknown tile '{}'", c),
+            ParseError::UnevenRow(row) => write!(f, "row {} has a different width", row + 1),
+        }
+    }
+}
+
 impl FromStr for Tile {
-    type Err = ();
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let char = s.chars().next().unwrap();
@@ -39,86 +130,104 @@ impl FromStr for Tile {
             ' ' => Ok(Tile::Empty),
             'p' => Ok(Tile::Player),
             'x' => Ok(Tile::Goal),
-            _ => {
-                trace(format!("Unknown tile: {}", char));
-                Err(())
-            }
+            _ => Err(ParseError::UnknownTile(char)),
         }
     }
 }
 
 impl Tile {
-    fn draw(&self, x: i32, y: i32, scale: u32) {
+    pub const ALL: [Tile; 9] = [
+        Tile::Empty,
+        Tile::VerticalWall,
+        Tile::HorizontalWall,
+        Tile::TopLeftCorner,
+        Tile::TopRightCorner,
+        Tile::BottomLeftCorner,
+        Tile::BottomRightCorner,
+        Tile::Player,
+        Tile::Goal,
+    ];
+
+    /// The character the tile is written as in `levels.rs`.
+    pub const fn symbol(&self) -> char {
         match self {
-            Tile::VerticalWall | Tile::HorizontalWall | Tile::BottomLeftCorner | Tile::BottomRightCorner | Tile::TopLeftCorner | Tile::TopRightCorner => {
-                unsafe {
-                    *DRAW_COLORS = 0x22;
-                }
-                rect(x, y, scale, scale);
-            },
-            Tile::Goal => {
-                unsafe {
-                    *DRAW_COLORS = 0x33;
-                }
-                oval(x, y, BALL_SIZE * scale, BALL_SIZE * scale);
-            }
-            _ => {}
+            Tile::VerticalWall => '|',
+            Tile::HorizontalWall => '-',
+            Tile::TopLeftCorner => 'l',
+            Tile::TopRightCorner => 'r',
+            Tile::BottomLeftCorner => 'L',
+            Tile::BottomRightCorner => 'R',
+            Tile::Empty => ' ',
+            Tile::Player => 'p',
+            Tile::Goal => 'x',
         }
     }
 
+    pub fn name(&self) -> &'static str {
+        match self {
+            Tile::VerticalWall => "Wall |",
+            Tile::HorizontalWall => "Wall -",
+            Tile::TopLeftCorner => "Corner l",
+            Tile::TopRightCorner => "Corner r",
+            Tile::BottomLeftCorner => "Corner L",
+            Tile::BottomRightCorner => "Corner R",
+            Tile::Empty => "Empty",
+            Tile::Player => "Tee",
+            Tile::Goal => "Cup",
+        }
+    }
+
+    /// Draws the tile's sprite, at either `SCALE` or `OVERVIEW_SCALE`.
+    pub fn draw(&self, x: i32, y: i32, scale: u32) {
+        let (sprite, flags) = match self {
+            Tile::VerticalWall => (Sprite::Wall, 0),
+            Tile::HorizontalWall => (Sprite::Wall, BLIT_ROTATE),
+            Tile::TopLeftCorner => (Sprite::Corner, 0),
+            Tile::TopRightCorner => (Sprite::Corner, BLIT_FLIP_X),
+            Tile::BottomLeftCorner => (Sprite::Corner, BLIT_FLIP_Y),
+            Tile::BottomRightCorner => (Sprite::Corner, BLIT_FLIP_X | BLIT_FLIP_Y),
+            Tile::Goal => (Sprite::Cup, 0),
+            Tile::Empty => (Sprite::Grass, 0),
+            Tile::Player => return,
+        };
+        sprites::draw(sprite, x, y, scale, flags);
+    }
+
+    /// What the tile is drawn as on the map, `None` for the background.
+    pub fn map_role(&self) -> Option<Role> {
+        match self {
+            Tile::Goal => Some(Role::Cup),
+            Tile::Player => Some(Role::Ball),
+            Tile::Empty => None,
+            _ => Some(Role::Wall),
+        }
+    }
+
+    pub fn is_wall(&self) -> bool {
+        matches!(
+            self,
+            Tile::VerticalWall
+                | Tile::HorizontalWall
+                | Tile::TopLeftCorner
+                | Tile::TopRightCorner
+                | Tile::BottomLeftCorner
+                | Tile::BottomRightCorner
+        )
+    }
+
     fn collision(&self, _x: f32, y: f32, vel_x: f32, vel_y: f32) -> (f32, f32) {
         match self {
-            Tile::VerticalWall => {
-                let speed = sqrtf(vel_x.powi(2) + vel_y.powi(2));
-                if speed > 0.05 {
-                    tone(
-                        (speed / MAX_SPEED * 100.0 + 450.0) as u32,
-                        1,
-                        (speed / MAX_SPEED * 50.0 + 50.0) as u32,
-                        TONE_TRIANGLE,
-                    );
-                }
-                (-vel_x, vel_y)
-            }
-            Tile::HorizontalWall => {
-                let speed = sqrtf(vel_x.powi(2) + vel_y.powi(2));
-                if speed > 0.05 {
-                    tone(
-                        (speed / MAX_SPEED * 100.0 + 450.0) as u32,
-                        1,
-                        (speed / MAX_SPEED * 50.0 + 50.0) as u32,
-                        TONE_TRIANGLE,
-                    );
-                }
-                (vel_x, -vel_y)
-            }
+            Tile::VerticalWall => (-vel_x, vel_y),
+            Tile::HorizontalWall => (vel_x, -vel_y),
             Tile::TopLeftCorner | Tile::TopRightCorner => {
-                let speed = sqrtf(vel_x.powi(2) + vel_y.powi(2));
-                if speed > 0.05 {
-                    tone(
-                        (speed / MAX_SPEED * 100.0 + 450.0) as u32,
-                        1,
-                        (speed / MAX_SPEED * 50.0 + 50.0) as u32,
-                        TONE_TRIANGLE,
-                    );
-                }
-                let mod_y = fmodf(y, 1.0);
+                let mod_y = y.fract();
                 if mod_y < 0.85 {
                     return (-vel_x, vel_y);
                 }
                 (vel_x, -vel_y)
             }
             Tile::BottomLeftCorner | Tile::BottomRightCorner => {
-                let speed = sqrtf(vel_x.powi(2) + vel_y.powi(2));
-                if speed > 0.05 {
-                    tone(
-                        (speed / MAX_SPEED * 100.0 + 450.0) as u32,
-                        1,
-                        (speed / MAX_SPEED * 50.0 + 50.0) as u32,
-                        TONE_TRIANGLE,
-                    );
-                }
-                let mod_y = fmodf(y, 1.0);
+                let mod_y = y.fract();
                 if mod_y > 0.15 {
                     return (-vel_x, vel_y);
                 }
@@ -135,145 +244,1236 @@ pub struct Level {
     pub width: u16,
 }
 
+/// Parses a level written as in `levels.rs`. Blank lines are skipped, so the
+/// leading newline is optional.
+impl FromStr for Level {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let rows: Vec<&str> = s.lines().filter(|line| !line.is_empty()).collect();
+        let width = rows.first().ok_or(ParseError::Empty)?.chars().count();
+        let mut tiles = Vec::with_capacity(width * rows.len());
+        for (i, row) in rows.iter().enumerate() {
+            if row.chars().count() != width {
+                return Err(ParseError::UnevenRow(i));
+            }
+            for c in row.chars() {
+                tiles.push(Tile::from_str(&c.to_string())?);
+            }
+        }
+        Ok(Level {
+            tiles,
+            width: width as u16,
+        })
+    }
+}
+
+/// Writes the level the way it is written in `levels.rs`, starting with a newline.
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for row in self.tiles.chunks(self.width as usize) {
+            f.write_char('\n')?;
+            for tile in row {
+                f.write_char(tile.symbol())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The ball hitting a wall.
+#[derive(Debug, Clone, Copy)]
+pub struct Impact {
+    pub position: Position,
+    pub speed: f32,
+}
+
+/// What happened to the ball during one frame of rolling.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Step {
+    /// The last time the ball hit a wall this frame.
+    pub impact: Option<Impact>,
+    pub sunk: bool,
+    /// The ball rolled off the level, where it stops.
+    pub off_course: bool,
+}
+
+impl Level {
+    pub fn tile_at(&self, x: f32, y: f32) -> Option<Tile> {
+        let rows = self.tiles.len() / self.width as usize;
+        let row = rows.checked_sub(y as usize)?;
+        self.tiles
+            .get(x as usize + row * self.width as usize)
+            .copied()
+    }
+
+    /// Advances the ball by one frame.
+    pub fn step(&self, position: &mut Position, velocity: &mut Position) -> Step {
+        let mut step = Step::default();
+
+        velocity.x *= DECCELERATION;
+        velocity.y *= DECCELERATION;
+
+        let speed = (velocity.x.powi(2) + velocity.y.powi(2)).sqrt();
+        let mut steps = (speed / 0.02) as u32;
+        if steps == 0 {
+            steps = 1;
+        }
+
+        if speed < 0.01 {
+            velocity.x = 0.0;
+            velocity.y = 0.0;
+        }
+
+        for _ in 0..steps {
+            position.x += velocity.x / steps as f32;
+            position.y += velocity.y / steps as f32;
+
+            if !self.contains(*position) {
+                velocity.x = 0.0;
+                velocity.y = 0.0;
+                step.off_course = true;
+                return step;
+            }
+            if let Some(tile) = self.tile_at(position.x, position.y) {
+                if tile == Tile::Goal {
+                    step.sunk = true;
+                    return step;
+                }
+                if tile.is_wall() {
+                    step.impact = Some(Impact {
+                        position: *position,
+                        speed: (velocity.x.powi(2) + velocity.y.powi(2)).sqrt(),
+                    });
+                }
+
+                (velocity.x, velocity.y) =
+                    tile.collision(position.x, position.y, velocity.x, velocity.y);
+            }
+        }
+        step
+    }
+
+    /// Advances the ball by one frame like `step`, and queues what happened to
+    /// it for the observers. `strokes` are those taken on the hole so far.
+    pub fn roll(
+        &self,
+        position: &mut Position,
+        velocity: &mut Position,
+        strokes: u16,
+        events: &mut Vec<Event>,
+    ) -> Step {
+        let was_moving = velocity.x != 0.0 || velocity.y != 0.0;
+        let step = self.step(position, velocity);
+        if let Some(impact) = step.impact {
+            events.push(Event::WallHit(impact));
+        }
+        if step.sunk {
+            events.push(Event::HoleSunk {
+                strokes,
+                ball: *position,
+                cup: self.cup_position().unwrap_or(*position),
+            });
+        } else if step.off_course {
+            events.push(Event::HazardEntered(*position));
+        } else if was_moving && velocity.x == 0.0 && velocity.y == 0.0 {
+            events.push(Event::BallStopped(*position));
+        }
+        step
+    }
+
+    /// Whether `position` is on one of the level's tiles. Rows are counted up
+    /// from the bottom, the bottom one starting at 1.
+    pub fn contains(&self, position: Position) -> bool {
+        position.x >= 0.0
+            && position.x < self.width as f32
+            && position.y >= 1.0
+            && position.y < self.rows() as f32 + 1.0
+    }
+
+    /// Draws the whole level with each tile `scale` pixels wide. Below a pixel
+    /// per tile, every tile that isn't empty still gets a pixel.
+    pub fn draw_map(&self, x: i32, y: i32, scale: f32, show_tee: bool) {
+        let edge = |i: usize| (i as f32 * scale).floor() as i32;
+        for (i, tile) in self.tiles.iter().enumerate() {
+            let Some(role) = tile.map_role() else {
+                continue;
+            };
+            if *tile == Tile::Player && !show_tee {
+                continue;
+            }
+            let (column, row) = (i % self.width as usize, i / self.width as usize);
+            theme::set_draw_colors(Some(role), Some(role));
+            rect(
+                x + edge(column),
+                y + edge(row),
+                (edge(column + 1) - edge(column)).max(1) as u32,
+                (edge(row + 1) - edge(row)).max(1) as u32,
+            );
+        }
+    }
+
+    /// Where the ball starts, `None` if the level has no tee.
+    pub fn tee_position(&self) -> Option<Position> {
+        let index = self.tiles.iter().position(|tile| *tile == Tile::Player)?;
+        let x = (index % self.width as usize) as f32;
+        let y = self.rows() as i32 - (index / self.width as usize) as i32;
+        Some(Position {
+            x: x + 0.5,
+            y: y as f32,
+        })
+    }
+
+    /// Middle of the cup, `None` if the level has none.
+    pub fn cup_position(&self) -> Option<Position> {
+        let index = self.tiles.iter().position(|tile| *tile == Tile::Goal)?;
+        let x = (index % self.width as usize) as f32;
+        let y = self.rows() as i32 - (index / self.width as usize) as i32;
+        Some(Position {
+            x: x + 0.5,
+            y: y as f32 + 0.5,
+        })
+    }
+
+    pub fn rows(&self) -> u16 {
+        (self.tiles.len() / self.width as usize) as u16
+    }
+
+    /// Fewest tiles the ball has to cross from the tee to the cup without going
+    /// through a wall, or `None` if the cup can't be reached.
+    pub fn path_length(&self) -> Option<u32> {
+        self.tiles
+            .iter()
+            .zip(self.distances())
+            .filter(|(tile, distance)| **tile == Tile::Goal && *distance != u16::MAX)
+            .map(|(_, distance)| distance as u32)
+            .min()
+    }
+
+    /// Tiles the ball can roll over from the tee, without crossing a wall.
+    pub fn open_area(&self) -> Vec<bool> {
+        self.distances()
+            .into_iter()
+            .map(|distance| distance != u16::MAX)
+            .collect()
+    }
+
+    /// Tiles crossed from the tee to each tile without going through a wall,
+    /// `u16::MAX` where the ball can't roll.
+    fn distances(&self) -> Vec<u16> {
+        let width = self.width as usize;
+        let mut distances = vec![u16::MAX; self.tiles.len()];
+        let Some(start) = self.tiles.iter().position(|tile| *tile == Tile::Player) else {
+            return distances;
+        };
+        distances[start] = 0;
+        // Spreads one tile further each pass instead of keeping a queue, which
+        // on a big level takes more than the heap has.
+        let mut distance = 0;
+        let mut spreading = true;
+        while spreading {
+            spreading = false;
+            for index in 0..self.tiles.len() {
+                if distances[index] != distance {
+                    continue;
+                }
+                let x = index % width;
+                let neighbours = [
+                    (x > 0).then(|| index - 1),
+                    (x + 1 < width).then_some(index + 1),
+                    index.checked_sub(width),
+                    Some(index + width),
+                ];
+                for next in neighbours.into_iter().flatten() {
+                    if next < self.tiles.len()
+                        && !self.tiles[next].is_wall()
+                        && distances[next] == u16::MAX
+                    {
+                        distances[next] = distance + 1;
+                        spreading = true;
+                    }
+                }
+            }
+            distance += 1;
+        }
+        distances
+    }
+}
+
+/// Velocity given to the ball by a drag of `x`, `y` screen pixels away from it.
+pub fn shot_velocity(x: i16, y: i16) -> Position {
+    let push_x = -x as f32 * PUSH_FORCE;
+    let push_y = y as f32 * PUSH_FORCE;
+    let speed = (push_x * push_x + push_y * push_y).sqrt();
+    if speed < MAX_SPEED {
+        Position {
+            x: push_x,
+            y: push_y,
+        }
+    } else {
+        Position {
+            x: push_x * MAX_SPEED / speed,
+            y: push_y * MAX_SPEED / speed,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
 pub struct Position {
     pub x: f32,
     pub y: f32,
 }
 
+/// Size in pixels of the rings around the ball and the cup when zoomed out.
+const MARKER_SIZE: u32 = 9;
+const MARKER_RADIUS: i32 = MARKER_SIZE as i32 / 2;
+/// Longest side of the minimap in pixels; bigger holes are shrunk to fit.
+const MINIMAP_SIZE: u16 = 40;
+/// Rows in pixels of the power meter and, below it, the score line.
+const POWER_METER_TOP: i32 = 141;
+const HUD_TOP: i32 = 152;
+/// Room in pixels kept between the cup arrow and the edges of the screen.
+const ARROW_MARGIN: f32 = 8.0;
+/// Bounds of the cup arrow, keeping the top left free for the pause button and
+/// the bottom for the power meter.
+const ARROW_LEFT: f32 = ARROW_MARGIN;
+const ARROW_RIGHT: f32 = SCREEN_SIZE as f32 - ARROW_MARGIN;
+const ARROW_TOP: f32 = 14.0;
+const ARROW_BOTTOM: f32 = POWER_METER_TOP as f32 - ARROW_MARGIN;
+/// Length of the cup arrow in pixels.
+const ARROW_SIZE: f32 = 6.0;
+/// Frames the cards before and after a hole are shown for.
+const INTRO_FRAMES: u16 = 90;
+const OUTRO_FRAMES: u16 = 120;
+/// Frames of rolling the aim preview looks ahead.
+const AIM_ASSIST_FRAMES: u32 = 120;
+/// Wall bounces the aim preview shows.
+const AIM_ASSIST_BOUNCES: u32 = 2;
+
+/// Length of the power meter's bar at full power.
+const POWER_METER_WIDTH: u32 = 50;
+
+/// State of the hole right before a shot, restored by a mulligan.
+pub struct Snapshot {
+    pub position: Position,
+    pub velocity: Position,
+    pub score: u16,
+    pub strokes: u16,
+    pub stats: Stats,
+}
+
 pub struct Game {
     pub state: State,
     pub level: u16,
+    /// Index into `levels::COURSES` of the course being played.
+    pub course: u16,
+    /// The holes of the current course, of which only the one in use is
+    /// parsed, see `load_level`. The endless mode keeps just its current hole.
     pub levels: Vec<Level>,
+    /// Seed of the endless mode's generated holes, `None` when playing a course.
+    pub endless: Option<u32>,
+    /// Date of the daily hole being played.
+    pub daily: Option<Date>,
+    /// Whether the hole being played comes from the editor.
+    pub custom: bool,
     pub score: u16,
     pub position: Position,
     pub velocity: Position,
-    pub scale: u8,
+    /// Hole the current round started on.
+    pub start_level: u16,
+    /// Frames since the current hole started.
+    pub frame: u32,
+    pub replay: Replay,
+    pub playback: Option<Playback>,
+    /// Strokes taken on the current hole.
+    pub strokes: u16,
+    pub ghost: Option<Ghost>,
+    pub save: Save,
+    pub difficulty: Difficulty,
+    /// Mulligans left this round.
+    pub mulligans: u8,
+    pub snapshot: Option<Snapshot>,
+    /// Whether the player wants the aim preview, where the difficulty allows it.
+    pub aim_assist: bool,
+    pub camera: Camera,
+    /// The hole's `Level::open_area`, the only tiles drawn with grass.
+    open_area: Vec<bool>,
+    /// The hole's `minimap_pixels`, worked out once rather than every frame.
+    minimap: Vec<u8>,
+    /// Events of this frame not yet handed to the observers, see
+    /// `dispatch_events`.
+    events: Vec<Event>,
+    audio: Audio,
+    pub effects: Effects,
+    pub stats: Stats,
+    pub achievements: Achievements,
+    /// The tallies as the current hole started, for `restart_hole`.
+    hole_stats: Stats,
+    /// Theme of the course being played, used unless another is picked in the
+    /// settings.
+    pub course_theme: &'static Theme,
+    pub transition: Option<Transition>,
+    /// Frames since `state` last changed.
+    pub state_frames: u16,
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Game {
     pub fn new() -> Self {
-        Self {
+        let mut game = Self {
             state: State::Menu,
             level: 0,
-            levels: levels::LEVELS
-                .iter()
-                .map(|level| Level {
-                    tiles: level
-                        .replace('\n', "")
-                        .chars()
-                        .filter(|c| c != &'\n')
-                        .map(|c| Tile::from_str(&c.to_string()).unwrap())
-                        .collect(),
-                    width: level.lines().nth(1).unwrap().len() as u16,
-                })
-                .collect(),
+            course: 0,
+            levels: Vec::new(),
+            endless: None,
+            daily: None,
+            custom: false,
             score: 0,
             position: Position { x: 0.0, y: 0.0 },
-            scale: 4,
             velocity: Position { x: 0.0, y: 0.0 },
+            start_level: 0,
+            frame: 0,
+            replay: Replay::default(),
+            playback: None,
+            strokes: 0,
+            ghost: None,
+            save: Save::load(),
+            difficulty: Difficulty::Normal,
+            mulligans: Difficulty::Normal.mulligans(),
+            snapshot: None,
+            aim_assist: true,
+            camera: Camera::new(),
+            open_area: Vec::new(),
+            minimap: Vec::new(),
+            events: Vec::new(),
+            audio: Audio,
+            effects: Effects::new(),
+            stats: Stats::default(),
+            achievements: Achievements::default(),
+            hole_stats: Stats::default(),
+            course_theme: levels::COURSES[0].theme,
+            transition: None,
+            state_frames: 0,
+        };
+        game.achievements = Achievements::new(game.save.achievements);
+        game.set_course(0);
+        game
+    }
+
+    pub fn set_course(&mut self, course: u16) {
+        let course_info = &levels::COURSES[course as usize];
+        self.course = course;
+        self.endless = None;
+        self.daily = None;
+        self.custom = false;
+        // Free the old holes first, the heap can't hold two courses at once.
+        self.levels = Vec::new();
+        self.levels = course_info
+            .holes
+            .iter()
+            .map(|_| Level {
+                tiles: Vec::new(),
+                width: 1,
+            })
+            .collect();
+        self.start_level = 0;
+        self.level = 0;
+        self.set_course_theme(course_info.theme);
+    }
+
+    /// Plays generated holes, one after another, until the player quits.
+    pub fn set_endless(&mut self, seed: u32) {
+        self.endless = Some(seed);
+        self.daily = None;
+        self.custom = false;
+        self.levels = vec![levelgen::generate(seed, 0)];
+        self.start_level = 0;
+        self.level = 0;
+        self.set_course_theme(&theme::DUSK);
+    }
+
+    /// Plays the single hole of `date`. Starting it uses up the day's attempt.
+    pub fn set_daily(&mut self, date: Date) {
+        self.endless = None;
+        self.daily = Some(date);
+        self.custom = false;
+        self.levels = vec![levelgen::generate(date.seed(), daily::DIFFICULTY)];
+        self.start_level = 0;
+        self.level = 0;
+        self.set_course_theme(&theme::DUSK);
+
+        self.save.daily_date = date.to_u32();
+        self.save.daily_score = 0;
+        self.save.store();
+    }
+
+    /// Test plays a level from the editor.
+    pub fn set_custom(&mut self, level: Level) {
+        self.endless = None;
+        self.daily = None;
+        self.custom = true;
+        self.levels = vec![level];
+        self.start_level = 0;
+        self.level = 0;
+        self.set_course_theme(&theme::DUSK);
+    }
+
+    fn set_course_theme(&mut self, theme: &'static Theme) {
+        self.course_theme = theme;
+        self.apply_theme();
+    }
+
+    /// The theme picked in the settings, `None` to use each course's own.
+    fn picked_theme(&self) -> Option<&'static Theme> {
+        let index = (self.save.theme as usize).checked_sub(1)?;
+        theme::THEMES.get(index).copied()
+    }
+
+    /// Switches to the theme picked in the settings, or the course's own.
+    pub fn apply_theme(&self) {
+        theme::set(self.picked_theme().unwrap_or(self.course_theme));
+    }
+
+    /// Name of the theme picked in the settings.
+    pub fn theme_name(&self) -> &'static str {
+        self.picked_theme().map_or("Course", |theme| theme.name)
+    }
+
+    /// Picks the next theme in the settings, then back to the course's own.
+    pub fn cycle_theme(&mut self) {
+        self.save.theme = (self.save.theme + 1) % (theme::THEMES.len() as u8 + 1);
+        self.save.store();
+        self.apply_theme();
+    }
+
+    /// Whether the holes are generated or from the editor, so no bests are kept.
+    pub fn is_unranked(&self) -> bool {
+        self.endless.is_some() || self.daily.is_some() || self.custom
+    }
+
+    /// Whether the play counts towards the stats and achievements. A replay
+    /// plays shots that were already counted, and a hole from the editor could
+    /// be built around any feat. Endless holes count, they're made by the game
+    /// like the courses.
+    fn is_counted(&self) -> bool {
+        self.playback.is_none() && !self.custom
+    }
+
+    /// The saved best of a course's hole. Other holes have none.
+    pub fn record(&self, level: u16) -> Option<&HoleRecord> {
+        if self.is_unranked() {
+            return None;
+        }
+        Some(&self.save.holes[levels::hole_index(self.course as usize, level as usize)])
+    }
+
+    /// Index into `levels` of the hole being played. The endless mode only
+    /// keeps the one hole it's on.
+    fn level_index(&self) -> usize {
+        if self.endless.is_some() {
+            0
+        } else {
+            self.level as usize
+        }
+    }
+
+    /// Parses hole `level` of the course and frees the one parsed before, as
+    /// a whole course of tiles doesn't fit in the heap.
+    pub fn load_level(&mut self, level: u16) {
+        if self.is_unranked() || !self.levels[level as usize].tiles.is_empty() {
+            return;
+        }
+        for other in &mut self.levels {
+            other.tiles = Vec::new();
         }
+        let hole = &levels::COURSES[self.course as usize].holes[level as usize];
+        self.levels[level as usize] = hole.level();
     }
 
     pub fn initialize_ball(&mut self) {
-        let player_index = self.levels[self.level as usize]
-            .tiles
+        self.load_level(self.level);
+        let level = &self.levels[self.level_index()];
+        self.position = level.tee_position().unwrap();
+        self.open_area = level.open_area();
+        self.minimap = minimap_pixels(level);
+        self.camera.snap(level, self.position);
+        self.events.push(Event::LevelChanged(self.level));
+        self.frame = 0;
+        self.strokes = 0;
+        self.snapshot = None;
+        self.hole_stats = self.stats;
+
+        self.ghost = match self.record(self.level) {
+            Some(record) if !record.ghost.shots.is_empty() => {
+                let cut_short = record.ghost.shots.len() < record.best as usize;
+                Some(Ghost::new(record.ghost.clone(), self.position, cut_short))
+            }
+            _ => None,
+        };
+    }
+
+    /// Hits the ball with a drag of `x`, `y` screen pixels away from it.
+    pub fn shoot(&mut self, x: i16, y: i16) {
+        let x = x.clamp(-MAX_DRAG, MAX_DRAG);
+        let y = y.clamp(-MAX_DRAG, MAX_DRAG);
+        self.snapshot = Some(Snapshot {
+            position: self.position,
+            velocity: self.velocity,
+            score: self.score,
+            strokes: self.strokes,
+            stats: self.stats,
+        });
+        self.velocity = shot_velocity(x, y);
+        self.camera.panning = false;
+        self.score += 1;
+        self.strokes += 1;
+        self.events.push(Event::ShotTaken { x, y });
+
+        if self.playback.is_none() {
+            self.replay.record(Shot {
+                level: self.level,
+                frame: self.frame,
+                x,
+                y,
+            });
+        }
+    }
+
+    /// Puts the ball back on the tee and forgets the strokes taken on this hole.
+    pub fn restart_hole(&mut self) {
+        self.score -= self.strokes;
+        let shots = self.replay.shots.len() - self.strokes as usize;
+        self.replay.shots.truncate(shots);
+        self.stats = self.hole_stats;
+        self.velocity.x = 0.0;
+        self.velocity.y = 0.0;
+        self.initialize_ball();
+    }
+
+    pub fn restart_round(&mut self) {
+        if let Some(seed) = self.endless {
+            self.set_endless(seed);
+        }
+        self.level = self.start_level;
+        self.score = 0;
+        self.velocity.x = 0.0;
+        self.velocity.y = 0.0;
+        self.mulligans = if self.daily.is_some() {
+            0
+        } else {
+            self.difficulty.mulligans()
+        };
+        self.stats = Stats::default();
+        self.replay.clear();
+        self.initialize_ball();
+    }
+
+    pub fn is_unlocked(&self, level: u16) -> bool {
+        level == 0 || self.record(level - 1).is_some_and(|record| record.best != 0)
+    }
+
+    /// Takes back the last shot of the hole if there are mulligans left. A shot
+    /// that sank can't be taken back, its best and ghost are already saved.
+    pub fn mulligan(&mut self) {
+        if self.mulligans == 0 || self.playback.is_some() || self.effects.sinking() {
+            return;
+        }
+        if let Some(snapshot) = self.snapshot.take() {
+            self.position = snapshot.position;
+            self.velocity = snapshot.velocity;
+            self.score = snapshot.score;
+            self.strokes = snapshot.strokes;
+            self.stats = snapshot.stats;
+            self.replay.shots.pop();
+            self.mulligans -= 1;
+        }
+    }
+
+    /// Restarts the round from `level` and plays the recorded shots back from there.
+    pub fn start_playback(&mut self, level: u16) {
+        let mut playback = Playback::new(self.replay.clone());
+        playback.seek(level);
+        self.score = self
+            .replay
+            .shots
             .iter()
-            .position(|tile| *tile == Tile::Player)
-            .unwrap();
+            .filter(|shot| shot.level < level)
+            .count() as u16;
+        self.playback = Some(playback);
+        self.state = State::Playing;
+        self.level = level;
+        self.velocity.x = 0.0;
+        self.velocity.y = 0.0;
+        self.initialize_ball();
+    }
 
-        let level = &self.levels[self.level as usize];
-        let player_x = (player_index % level.width as usize) as f32;
-        let player_y = level.tiles.len() as i32 / level.width as i32
-            - (player_index / level.width as usize) as i32;
-        self.position.x = player_x + 0.5;
-        self.position.y = player_y as f32;
+    pub fn stop_playback(&mut self) {
+        self.playback = None;
+        self.state = State::GameOver;
+    }
+
+    /// Takes the next recorded shot once it is due.
+    pub fn play_recorded_shot(&mut self) {
+        if !self.is_stationary() {
+            return;
+        }
+        let shot = match self.playback.as_mut() {
+            Some(playback) => playback.next_shot(self.level, self.frame),
+            None => None,
+        };
+        if let Some(shot) = shot {
+            self.shoot(shot.x, shot.y);
+        }
+    }
+
+    pub fn shows_aim_assist(&self) -> bool {
+        self.aim_assist && self.difficulty.allows_aim_assist()
+    }
+
+    /// Dots along the path a drag of `x`, `y` would send the ball, up to its
+    /// second bounce.
+    pub fn draw_aim_assist(&self, x: i16, y: i16) {
+        let level = &self.levels[self.level_index()];
+        let mut position = self.position;
+        let mut velocity =
+            shot_velocity(x.clamp(-MAX_DRAG, MAX_DRAG), y.clamp(-MAX_DRAG, MAX_DRAG));
+        let mut bounces = 0;
+        let camera = self.camera;
+        theme::set_draw_colors(Some(Role::Highlight), Some(Role::Highlight));
+        for frame in 1..=AIM_ASSIST_FRAMES {
+            let step = level.step(&mut position, &mut velocity);
+            if step.impact.is_some() {
+                bounces += 1;
+            }
+            let stopped = velocity.x == 0.0 && velocity.y == 0.0;
+            if step.sunk || step.off_course || stopped || bounces > AIM_ASSIST_BOUNCES {
+                break;
+            }
+            if frame % 3 == 0 {
+                let (x, y) = camera.screen_position(position);
+                rect(x, y, 1, 1);
+            }
+        }
+    }
+
+    /// Shows how hard a drag of `x`, `y` hits the ball, as a share of
+    /// `MAX_SPEED`, and the direction it goes in.
+    pub fn draw_power_meter(&self, x: i16, y: i16) {
+        let push = (x as f32 * x as f32 + y as f32 * y as f32).sqrt() * PUSH_FORCE;
+        let clamped = push >= MAX_SPEED || x.abs() > MAX_DRAG || y.abs() > MAX_DRAG;
+        let power = push.min(MAX_SPEED) / MAX_SPEED;
+        let velocity = shot_velocity(x.clamp(-MAX_DRAG, MAX_DRAG), y.clamp(-MAX_DRAG, MAX_DRAG));
+        let angle = (atan2f(velocity.y, velocity.x).to_degrees().round() as i32).rem_euclid(360);
+
+        theme::set_draw_colors(None, Some(Role::Highlight));
+        rect(0, POWER_METER_TOP, POWER_METER_WIDTH + 2, 7);
+        let role = if clamped { Role::Title } else { Role::Highlight };
+        theme::set_draw_colors(Some(role), Some(role));
+        rect(1, POWER_METER_TOP + 1, (power * POWER_METER_WIDTH as f32) as u32, 5);
+        theme::set_color(Role::Highlight);
+        text(
+            format!("{:3}% {:3}\u{b0}", (power * 100.0) as u32, angle),
+            POWER_METER_WIDTH as i32 + 6,
+            POWER_METER_TOP,
+        );
     }
 
     pub fn is_stationary(&self) -> bool {
         self.velocity.x == 0.0 && self.velocity.y == 0.0
     }
 
+    /// Moves the camera away from the ball until the next shot is aimed.
+    pub fn pan_camera(&mut self, x: f32, y: f32) {
+        self.camera.pan(&self.levels[self.level_index()], x, y);
+    }
+
     pub fn next_level(&mut self) {
-        if self.level == levels::LEVELS.len() as u16 - 1 {
+        if let Some(seed) = self.endless {
+            // Only the hole being played is kept around, and freed before the
+            // next one is built.
+            self.levels.clear();
+            self.level += 1;
+            let difficulty = (self.level / 3).min(levelgen::MAX_DIFFICULTY as u16) as u8;
+            self.levels.push(levelgen::generate(
+                seed.wrapping_add(self.level as u32),
+                difficulty,
+            ));
+            // There's no end to replay, so only the shots of the hole being
+            // played are kept, for mulligans and restarts.
+            self.replay.clear();
+            self.velocity.x = 0.0;
+            self.velocity.y = 0.0;
+            self.initialize_ball();
+            self.state = State::HoleIntro;
+        } else if self.custom {
+            self.state = State::Editor;
+        } else if self.level == self.levels.len() as u16 - 1 {
             self.state = State::GameOver;
+            if self.playback.take().is_none() {
+                // Course replays can be played back with `leveltool replay`.
+                match self.daily {
+                    Some(_) => trace(format!("Replay: {}", self.replay.to_hex())),
+                    None => trace(format!(
+                        "Replay of {}: {}",
+                        levels::COURSES[self.course as usize].name,
+                        self.replay.to_hex()
+                    )),
+                }
+                self.record_round();
+            }
         } else {
             self.level += 1;
             self.velocity.x = 0.0;
             self.velocity.y = 0.0;
             self.initialize_ball();
+            self.state = State::HoleIntro;
+        }
+    }
+
+    /// Par of the hole being played, if it's a course hole.
+    pub fn par(&self) -> Option<u8> {
+        if self.is_unranked() {
+            return None;
+        }
+        Some(levels::COURSES[self.course as usize].holes[self.level as usize].par)
+    }
+
+    /// Shows which hole is coming up for a moment, or until `skip`.
+    pub fn update_intro(&mut self, skip: bool) {
+        self.draw();
+        let title = if self.daily.is_some() {
+            String::from("Daily hole")
+        } else if self.custom {
+            String::from("Test play")
+        } else {
+            format!("Hole {}", self.level + 1)
+        };
+        let detail = match self.par() {
+            Some(par) => format!("Par {}", par),
+            None => String::new(),
+        };
+        draw_card(&title, &detail);
+
+        self.state_frames += 1;
+        if skip || self.state_frames >= INTRO_FRAMES {
+            self.state = State::Playing;
+        }
+    }
+
+    /// Shows how the hole went for a moment, or until `skip`, then closes in on
+    /// the cup and moves on.
+    pub fn update_outro(&mut self, skip: bool) {
+        self.draw();
+        let strokes = match self.strokes {
+            1 => String::from("1 stroke"),
+            strokes => format!("{} strokes", strokes),
+        };
+        let detail = match self.par() {
+            _ if self.strokes == 1 => String::from("Hole in one!"),
+            Some(par) => format!("Par {}: {}", par, score_name(self.strokes, par)),
+            None => String::new(),
+        };
+        draw_card(&strokes, &detail);
+        let unlocked: Vec<&str> = Achievement::ALL
+            .iter()
+            .filter(|achievement| self.achievements.is_new(**achievement))
+            .map(|achievement| achievement.name())
+            .collect();
+        if self.is_counted() && !unlocked.is_empty() {
+            draw_unlocked(&unlocked);
+        }
+
+        self.state_frames += 1;
+        match self.transition {
+            Some(transition) if transition.is_closing() && transition.is_done() => {
+                self.next_level()
+            }
+            Some(transition) if transition.is_closing() => {}
+            _ if skip || self.state_frames >= OUTRO_FRAMES => {
+                let (x, y) = self.camera.screen_position(self.position);
+                self.transition = Some(Transition::closing(Kind::Iris { x, y }));
+            }
+            _ => {}
+        }
+    }
+
+    /// Starts the transition that fits moving from `previous` to the current state.
+    pub fn state_changed(&mut self, previous: State) {
+        self.state_frames = 0;
+        let kind = match (previous, self.state) {
+            (State::Playing, State::Paused) | (State::Paused, State::Playing) => return,
+            (State::HoleIntro, State::Playing) | (State::Playing, State::HoleOutro) => return,
+            (_, State::HoleIntro) => {
+                let (x, y) = self.camera.screen_position(self.position);
+                Kind::Iris { x, y }
+            }
+            (_, State::Menu | State::GameOver | State::Playing) => Kind::Fade,
+            _ => Kind::Wipe,
+        };
+        self.transition = Some(Transition::opening(kind));
+    }
+
+    /// Draws the transition under way over the frame, if there is one.
+    pub fn draw_transition(&mut self) {
+        let palette = theme::current().palette;
+        unsafe {
+            *PALETTE = palette;
+        }
+        if let Some(transition) = &mut self.transition {
+            transition.update(palette);
+            if transition.is_done() && !transition.is_closing() {
+                self.transition = None;
+            }
         }
     }
 
     pub fn update(&mut self) {
-        self.velocity.x *= DECCELERATION;
-        self.velocity.y *= DECCELERATION;
+        let level = &self.levels[self.level_index()];
+        // The hole is only left once the ball has dropped into the cup.
+        if self.effects.sinking() {
+            self.effects.update(self.position, self.velocity);
+            if !self.effects.sinking() {
+                self.state = State::HoleOutro;
+            }
+            return;
+        }
 
-        let speed = sqrtf(self.velocity.x.powi(2) + self.velocity.y.powi(2));
-        let mut steps = (speed / 0.02) as u32;
-        if steps == 0 {
-            steps = 1;
+        self.frame += 1;
+        if let Some(ghost) = &mut self.ghost {
+            ghost.update(level, self.level, self.frame);
         }
 
-        if speed < 0.01 {
-            self.velocity.x = 0.0;
-            self.velocity.y = 0.0;
+        let step = level.roll(
+            &mut self.position,
+            &mut self.velocity,
+            self.strokes,
+            &mut self.events,
+        );
+        if step.off_course {
+            // The shot counts, and the ball is played again from where it was hit.
+            if let Some(snapshot) = &self.snapshot {
+                self.position = snapshot.position;
+            }
         }
+        self.camera.update(level, self.position, self.velocity);
+        self.effects.update(self.position, self.velocity);
+        self.dispatch_events();
 
-        for _ in 0..steps {
-            self.position.x += self.velocity.x / steps as f32;
-            self.position.y += self.velocity.y / steps as f32;
-
-            let tile_index = self.position.x as usize
-                + (self.levels[self.level as usize].tiles.len() as usize
-                    / self.levels[self.level as usize].width as usize
-                    - self.position.y as usize)
-                    * self.levels[self.level as usize].width as usize;
-            if tile_index < self.levels[self.level as usize].tiles.len() {
-                if self.levels[self.level as usize].tiles[tile_index] == Tile::Goal {
-                    tone(600, 1, 100, TONE_PULSE1);
-                    self.next_level();
-                    return;
-                }
-                let tile = &self.levels[self.level as usize].tiles[tile_index];
-
-                (self.velocity.x, self.velocity.y) = tile.collision(
-                    self.position.x,
-                    self.position.y,
-                    self.velocity.x,
-                    self.velocity.y,
-                );
+        if step.sunk {
+            self.record_best();
+            if !self.effects.sinking() {
+                self.state = State::HoleOutro;
+            }
+        }
+    }
+
+    /// Hands every queued event to the audio, the effects, the statistics and
+    /// the achievements, in the order they happened.
+    pub fn dispatch_events(&mut self) {
+        for event in std::mem::take(&mut self.events) {
+            let observers: [&mut dyn Observer; 2] = [&mut self.audio, &mut self.effects];
+            for observer in observers {
+                observer.notify(&event);
+            }
+            if self.is_counted() {
+                self.stats.notify(&event);
+                self.achievements.notify(&event);
             }
         }
+        if self.achievements.unlocked != self.save.achievements {
+            self.save.achievements = self.achievements.unlocked;
+            self.save.store();
+        }
+    }
+
+    /// Keeps this attempt as the hole's ghost if it beat the best score on disk.
+    fn record_best(&mut self) {
+        if self.playback.is_some() || self.is_unranked() {
+            return;
+        }
+        let record =
+            &mut self.save.holes[levels::hole_index(self.course as usize, self.level as usize)];
+        if record.best != 0 && record.best as u16 <= self.strokes {
+            return;
+        }
+        record.best = self.strokes.min(u8::MAX as u16) as u8;
+        // A best of more shots than fit keeps its first ones, for a ghost that
+        // shows the way in but never sinks.
+        let shots = &self.replay.shots[self.replay.shots.len() - self.strokes as usize..];
+        record.ghost = Replay {
+            shots: shots[..shots.len().min(MAX_GHOST_SHOTS)].to_vec(),
+        };
+        self.save.store();
+    }
+
+    /// Keeps the score of a full round if it beat the course's best.
+    fn record_round(&mut self) {
+        if self.custom {
+            return;
+        }
+        if self.daily.is_some() {
+            self.save.daily_score = self.score.min(u8::MAX as u16) as u8;
+            self.save.store();
+            return;
+        }
+        if self.start_level != 0 {
+            return;
+        }
+        let best = &mut self.save.courses[self.course as usize];
+        if *best == 0 || self.score < *best {
+            *best = self.score;
+            self.save.store();
+        }
+    }
+
+    /// Draws the hole at a pixel per tile or less in the top right corner, with
+    /// the ball in place of the tee.
+    fn draw_minimap(&self) {
+        let level = &self.levels[self.level_index()];
+        let (scale, width, height) = minimap_size(level);
+        let (width, height) = (width as i32, height as i32);
+        let (x, y) = (SCREEN_SIZE as i32 - width - 1, 10);
+
+        theme::set_draw_colors(Some(Role::Background), Some(Role::Background));
+        rect(x - 1, y - 1, width as u32 + 2, height as u32 + 2);
+        unsafe {
+            let cup = theme::color(Role::Cup);
+            *DRAW_COLORS = cup << 12 | cup << 8 | theme::color(Role::Wall) << 4;
+        }
+        blit(&self.minimap, x, y, width as u32, height as u32, BLIT_2BPP);
+
+        // Rows count from the top, the ball's y from the bottom.
+        let ball_x = (self.position.x * scale).floor() as i32;
+        let ball_y = ((level.rows() as f32 + 1.0 - self.position.y) * scale).floor() as i32;
+        theme::set_draw_colors(Some(Role::Ball), Some(Role::Ball));
+        rect(x + ball_x.max(0).min(width - 1), y + ball_y.max(0).min(height - 1), 1, 1);
+    }
+
+    /// Left and bottom edges of the minimap's background, `None` while it's hidden.
+    fn minimap_bounds(&self) -> Option<(f32, f32)> {
+        // The overview already shows the whole hole.
+        if self.camera.scale < SCALE as f32 {
+            return None;
+        }
+        let (_, width, height) = minimap_size(&self.levels[self.level_index()]);
+        Some(((SCREEN_SIZE - width - 2) as f32, (height + 11) as f32))
+    }
+
+    /// Points to the cup from the edge of the screen while it's out of view,
+    /// with how many tiles away it is.
+    fn draw_cup_arrow(&self) {
+        let Some(cup) = self.levels[self.level_index()].cup_position() else {
+            return;
+        };
+        let (x, y) = self.camera.screen_position(cup);
+        let (x, y) = (x as f32, y as f32);
+        // The minimap cuts the top right corner out of the bounds.
+        let minimap = self
+            .minimap_bounds()
+            .map(|(left, bottom)| (left - ARROW_MARGIN, bottom + ARROW_MARGIN));
+        let under_minimap = minimap.is_some_and(|(left, bottom)| x >= left && y <= bottom);
+        if (ARROW_LEFT..=ARROW_RIGHT).contains(&x)
+            && (ARROW_TOP..=ARROW_BOTTOM).contains(&y)
+            && !under_minimap
+        {
+            return;
+        }
+
+        // Walk from the middle of the screen towards the cup until the box.
+        let middle = SCREEN_SIZE as f32 / 2.0;
+        let (dx, dy) = (x - middle, y - middle);
+        let length = (dx * dx + dy * dy).sqrt();
+        let (dx, dy) = (dx / length, dy / length);
+        let limit = |d: f32, low: f32, high: f32| {
+            if d > 0.0 {
+                (high - middle) / d
+            } else if d < 0.0 {
+                (low - middle) / d
+            } else {
+                f32::MAX
+            }
+        };
+        let mut t = limit(dx, ARROW_LEFT, ARROW_RIGHT).min(limit(dy, ARROW_TOP, ARROW_BOTTOM));
+        if let Some((left, bottom)) = minimap {
+            if dx > 0.0 && dy < 0.0 {
+                // Stop where the walk enters the corner, past its left or bottom edge.
+                t = t.min(((left - middle) / dx).max((bottom - middle) / dy));
+            }
+        }
+        let (tip_x, tip_y) = (middle + dx * t, middle + dy * t);
+        let (base_x, base_y) = (tip_x - dx * ARROW_SIZE, tip_y - dy * ARROW_SIZE);
+        let (side_x, side_y) = (-dy * ARROW_SIZE / 2.0, dx * ARROW_SIZE / 2.0);
+
+        theme::set_color(Role::Cup);
+        let point = |x: f32, y: f32| (x as i32, y as i32);
+        let tip = point(tip_x, tip_y);
+        let left = point(base_x + side_x, base_y + side_y);
+        let right = point(base_x - side_x, base_y - side_y);
+        line(tip.0, tip.1, left.0, left.1);
+        line(tip.0, tip.1, right.0, right.1);
+        line(left.0, left.1, right.0, right.1);
+
+        let distance =
+            ((cup.x - self.position.x).powi(2) + (cup.y - self.position.y).powi(2)).sqrt();
+        let label = format!("{}", distance as u32);
+        let width = label.len() as i32 * 8;
+        let label_x = (base_x - dx * 8.0) as i32 - width / 2;
+        let label_y = (base_y - dy * 8.0) as i32 - 4;
+        theme::set_draw_colors(None, Some(Role::Cup));
+        text(
+            &label,
+            label_x.max(0).min(SCREEN_SIZE as i32 - width),
+            label_y.clamp(0, SCREEN_SIZE as i32 - 8),
+        );
     }
 
     pub fn draw(&mut self) {
-        let level = &self.levels[self.level as usize];
-        for (i, tile) in level.tiles.iter().enumerate() {
-            let x = (i % level.width as usize) as i32;
-            let y =
-                level.tiles.len() as i32 / level.width as i32 - (i / level.width as usize) as i32;
-            let scale = self.scale as u32;
-
-            let x_coord =
-                SCREEN_SIZE as i32 / 2 + x * scale as i32 - (self.position.x * scale as f32) as i32;
-            let y_coord = SCREEN_SIZE as i32 / 2 - y * scale as i32
-                + (self.position.y * scale as f32) as i32 - BALL_SIZE as i32 * SCALE as i32 / 2 - 1;
-            tile.draw(x_coord, y_coord, scale);
-
-            unsafe {
-                *DRAW_COLORS = 0x44;
+        let level = &self.levels[self.level_index()];
+        let camera = self.camera;
+        let (columns, rows) = camera.visible_tiles(level);
+        for row in rows {
+            for column in columns.clone() {
+                let index = (row * level.width + column) as usize;
+                let tile = level.tiles[index];
+                let (x, y) = camera.tile_screen_position(level, column, row);
+                if camera.uses_sprites() {
+                    // Outside the walls is left as background.
+                    if tile != Tile::Empty || self.open_area[index] {
+                        tile.draw(x, y, camera.scale as u32);
+                    }
+                    continue;
+                }
+                // Between the sprite scales tiles are blocks of colour, at least a
+                // pixel each so that walls of holes shrunk below a pixel per tile
+                // don't vanish.
+                let Some(role) = tile.map_role().filter(|_| tile != Tile::Player) else {
+                    continue;
+                };
+                let (right, bottom) = camera.tile_screen_position(level, column + 1, row + 1);
+                theme::set_draw_colors(Some(role), Some(role));
+                rect(x, y, (right - x).max(1) as u32, (bottom - y).max(1) as u32);
             }
-            oval(
-                SCREEN_SIZE as i32 / 2 - (BALL_SIZE * scale) as i32 / 2,
-                SCREEN_SIZE as i32 / 2 - (BALL_SIZE * scale) as i32 / 2,
-                BALL_SIZE * scale,
-                BALL_SIZE * scale,
-            );
-            unsafe {
-                *DRAW_COLORS = 0x30;
+        }
+
+        self.effects.draw_behind(&camera);
+        let size = ((BALL_SIZE as f32 * camera.scale) as u32).max(1);
+        let radius = size as i32 / 2;
+        let (x, y) = camera.screen_position(self.position);
+        // While sinking the effects draw the ball dropping into the cup, and
+        // after that it's gone.
+        if !self.effects.sinking() && self.state != State::HoleOutro {
+            if camera.uses_sprites() {
+                sprites::draw(Sprite::Ball, x - radius, y - radius, size, 0);
+            } else {
+                theme::set_draw_colors(Some(Role::Ball), Some(Role::Ball));
+                rect(x - radius, y - radius, size, size);
             }
-            text(format!("Level:{}", self.level + 1).as_str(), 104, 152);
-            text(format!("Score:{}", self.score).as_str(), 0, 152);
         }
+        self.effects.draw_ball(&camera, self.position, self.velocity);
+
+        // Zoomed out the ball and the cup are only a pixel or two, so ring them.
+        if camera.scale < SCALE as f32 {
+            theme::set_draw_colors(None, Some(Role::Ball));
+            oval(x - MARKER_RADIUS, y - MARKER_RADIUS, MARKER_SIZE, MARKER_SIZE);
+            if let Some(cup) = level.cup_position() {
+                let (x, y) = camera.screen_position(cup);
+                theme::set_draw_colors(None, Some(Role::Cup));
+                oval(x - MARKER_RADIUS, y - MARKER_RADIUS, MARKER_SIZE, MARKER_SIZE);
+            }
+        }
+
+        theme::set_draw_colors(None, Some(Role::Highlight));
+        text(format!("Level:{}", self.level + 1).as_str(), 104, HUD_TOP);
+        text(format!("Score:{}", self.score).as_str(), 0, HUD_TOP);
+
+        if self.difficulty.mulligans() > 0 && self.playback.is_none() {
+            theme::set_draw_colors(None, Some(Role::Highlight));
+            text(format!("Undo:{}", self.mulligans).as_str(), 112, 0);
+        }
+
+        if self.minimap_bounds().is_some() {
+            self.draw_minimap();
+        }
+        self.draw_cup_arrow();
+
+        if let Some(ghost) = &self.ghost {
+            if !ghost.sunk && !ghost.stopped {
+                let (x, y) = camera.screen_position(ghost.position);
+                let role = if ghost.cut_short { Role::Highlight } else { Role::Title };
+                theme::set_draw_colors(None, Some(role));
+                oval(x - radius, y - radius, size, size);
+            }
+        }
+    }
+}
+
+/// What a score of `strokes` on a hole with `par` is called.
+fn score_name(strokes: u16, par: u8) -> &'static str {
+    match strokes as i32 - par as i32 {
+        ..=-3 => "Albatross",
+        -2 => "Eagle",
+        -1 => "Birdie",
+        0 => "Par",
+        1 => "Bogey",
+        2 => "Double bogey",
+        _ => "Over par",
+    }
+}
+
+/// Scale of the minimap of `level`, at a pixel per tile or less, and its width
+/// and height in pixels.
+fn minimap_size(level: &Level) -> (f32, u32, u32) {
+    let scale = (MINIMAP_SIZE as f32 / level.width.max(level.rows()) as f32).min(1.0);
+    let width = (level.width as f32 * scale).ceil() as u32;
+    let height = (level.rows() as f32 * scale).ceil() as u32;
+    (scale, width, height)
+}
+
+/// The minimap of `level` as a 2bpp image, 1 for walls and 2 for the cup. Every
+/// tile that isn't empty marks the pixel it falls in, so thin walls still show
+/// on holes that are shrunk. A wall and the cup sharing a pixel make 3, drawn
+/// as the cup.
+fn minimap_pixels(level: &Level) -> Vec<u8> {
+    let (_, width, height) = minimap_size(level);
+    let (columns, rows) = (level.width as u32, level.rows() as u32);
+    let mut pixels = vec![0u8; (width * height).div_ceil(4) as usize];
+    for (i, tile) in (0..).zip(&level.tiles) {
+        let value = match tile {
+            Tile::Empty | Tile::Player => continue,
+            Tile::Goal => 2,
+            _ => 1,
+        };
+        let pixel = i / columns * height / rows * width + i % columns * width / columns;
+        // The leftmost pixel of a byte is in its top bits.
+        pixels[pixel as usize / 4] |= value << (6 - pixel % 4 * 2);
+    }
+    pixels
+}
+
+/// A box in the middle of the screen with a line of text and one below it.
+fn draw_card(title: &str, detail: &str) {
+    theme::set_draw_colors(Some(Role::Background), Some(Role::Text));
+    rect(16, 60, 128, 36);
+    let centered = |line: &str| 80 - line.len() as i32 * 4;
+    theme::set_color(Role::Title);
+    text(title, centered(title), 66);
+    theme::set_color(Role::Highlight);
+    text(detail, centered(detail), 80);
+}
+
+/// Lists the achievements unlocked on the hole under its card.
+fn draw_unlocked(names: &[&str]) {
+    theme::set_draw_colors(Some(Role::Background), Some(Role::Text));
+    rect(16, 100, 128, 16 + names.len() as u32 * 10);
+    theme::set_color(Role::Title);
+    text("Unlocked", 48, 104);
+    theme::set_color(Role::Highlight);
+    for (name, y) in names.iter().zip((116..).step_by(10)) {
+        text(name, 80 - name.len() as i32 * 4, y);
     }
 }
@@ -0,0 +1,52 @@
+use crate::game::{shot_velocity, Level, Position};
+use crate::replay::{Playback, Replay};
+
+/// Replays the best attempt on a hole alongside the player's ball.
+pub struct Ghost {
+    pub position: Position,
+    pub velocity: Position,
+    pub sunk: bool,
+    /// Whether only the first shots of the best attempt were kept, see
+    /// `save::MAX_GHOST_SHOTS`. Such a ghost is told apart and leaves once it runs out.
+    pub cut_short: bool,
+    /// Set once a ghost that was cut short has played its last shot and stopped.
+    pub stopped: bool,
+    /// Where the last shot was taken from, for a ball that rolls off the course.
+    from: Position,
+    playback: Playback,
+}
+
+impl Ghost {
+    pub fn new(replay: Replay, start: Position, cut_short: bool) -> Self {
+        Self {
+            position: start,
+            velocity: Position { x: 0.0, y: 0.0 },
+            sunk: false,
+            cut_short,
+            stopped: false,
+            from: start,
+            playback: Playback::new(replay),
+        }
+    }
+
+    pub fn update(&mut self, level: &Level, level_index: u16, frame: u32) {
+        if self.sunk || self.stopped {
+            return;
+        }
+        if self.velocity.x == 0.0 && self.velocity.y == 0.0 {
+            if self.cut_short && self.playback.is_finished() {
+                self.stopped = true;
+                return;
+            }
+            if let Some(shot) = self.playback.next_shot(level_index, frame) {
+                self.velocity = shot_velocity(shot.x, shot.y);
+                self.from = self.position;
+            }
+        }
+        let step = level.step(&mut self.position, &mut self.velocity);
+        self.sunk = step.sunk;
+        if step.off_course {
+            self.position = self.from;
+        }
+    }
+}
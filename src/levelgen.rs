@@ -0,0 +1,243 @@
+use crate::game::{Level, Tile};
+
+pub const MAX_DIFFICULTY: u8 = 5;
+/// Attempts at a layout before falling back to a plain room.
+const ATTEMPTS: u32 = 16;
+
+/// Xorshift generator, so the same seed always builds the same hole.
+pub struct Rng(u32);
+
+impl Rng {
+    pub fn new(seed: u32) -> Self {
+        // Xorshift gets stuck on 0, and small seeds start out poorly mixed.
+        Self(seed.wrapping_mul(0x9e37_79b9) | 1)
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        self.0
+    }
+
+    /// A number in `min..max`.
+    pub fn range(&mut self, min: u32, max: u32) -> u32 {
+        min + self.next_u32() % (max - min).max(1)
+    }
+}
+
+struct Room {
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+}
+
+impl Room {
+    fn center(&self) -> (u32, u32) {
+        (self.x + self.width / 2, self.y + self.height / 2)
+    }
+}
+
+/// Carves rooms and corridors into a solid grid, then turns the edges of the
+/// open area into walls.
+struct Layout {
+    width: u32,
+    height: u32,
+    open: Vec<bool>,
+}
+
+impl Layout {
+    fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            open: vec![false; (width * height) as usize],
+        }
+    }
+
+    fn is_open(&self, x: i32, y: i32) -> bool {
+        x >= 0
+            && y >= 0
+            && (x as u32) < self.width
+            && (y as u32) < self.height
+            && self.open[(y as u32 * self.width + x as u32) as usize]
+    }
+
+    fn fill(&mut self, x: u32, y: u32, width: u32, height: u32, open: bool) {
+        // Keep a solid border for the outer walls.
+        for row in y.max(1)..(y + height).min(self.height - 1) {
+            for column in x.max(1)..(x + width).min(self.width - 1) {
+                self.open[(row * self.width + column) as usize] = open;
+            }
+        }
+    }
+
+    /// Whether the 3x3 block around `x`, `y` is open, so the ball and the cup
+    /// never sit right next to a wall.
+    fn is_clear(&self, x: u32, y: u32) -> bool {
+        (-1..=1).all(|dy| (-1..=1).all(|dx| self.is_open(x as i32 + dx, y as i32 + dy)))
+    }
+
+    fn wall(&self, x: i32, y: i32) -> Tile {
+        let left = self.is_open(x - 1, y);
+        let right = self.is_open(x + 1, y);
+        let up = self.is_open(x, y - 1);
+        let down = self.is_open(x, y + 1);
+
+        match (left || right, up || down) {
+            (true, true) if up && down => Tile::HorizontalWall,
+            (true, true) if up && left => Tile::TopLeftCorner,
+            (true, true) if up => Tile::TopRightCorner,
+            (true, true) if left => Tile::BottomLeftCorner,
+            (true, true) => Tile::BottomRightCorner,
+            (true, false) => Tile::VerticalWall,
+            (false, true) => Tile::HorizontalWall,
+            (false, false) => {
+                if self.is_open(x + 1, y + 1) {
+                    Tile::TopLeftCorner
+                } else if self.is_open(x - 1, y + 1) {
+                    Tile::TopRightCorner
+                } else if self.is_open(x + 1, y - 1) {
+                    Tile::BottomLeftCorner
+                } else if self.is_open(x - 1, y - 1) {
+                    Tile::BottomRightCorner
+                } else {
+                    Tile::Empty
+                }
+            }
+        }
+    }
+
+    /// Builds the level, cropped to the walls around the open area.
+    fn to_level(&self, player: (u32, u32), goal: (u32, u32)) -> Level {
+        let tile = |x: u32, y: u32| {
+            if (x, y) == player {
+                Tile::Player
+            } else if (x, y) == goal {
+                Tile::Goal
+            } else if self.is_open(x as i32, y as i32) {
+                Tile::Empty
+            } else {
+                self.wall(x as i32, y as i32)
+            }
+        };
+
+        let (mut left, mut top, mut right, mut bottom) = (self.width, self.height, 0, 0);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if tile(x, y) != Tile::Empty {
+                    left = left.min(x);
+                    top = top.min(y);
+                    right = right.max(x);
+                    bottom = bottom.max(y);
+                }
+            }
+        }
+
+        let mut tiles = Vec::with_capacity(((right - left + 1) * (bottom - top + 1)) as usize);
+        for y in top..=bottom {
+            for x in left..=right {
+                tiles.push(tile(x, y));
+            }
+        }
+        Level {
+            tiles,
+            width: (right - left + 1) as u16,
+        }
+    }
+}
+
+/// Builds a hole from `seed`. Higher `difficulty` (up to `MAX_DIFFICULTY`)
+/// means bigger holes with more rooms and obstacles.
+pub fn generate(seed: u32, difficulty: u8) -> Level {
+    let difficulty = difficulty.min(MAX_DIFFICULTY) as u32;
+    let mut rng = Rng::new(seed);
+    for _ in 0..ATTEMPTS {
+        if let Some(level) = attempt(&mut rng, difficulty) {
+            if level.path_length().is_some() {
+                return level;
+            }
+        }
+    }
+    fallback()
+}
+
+fn attempt(rng: &mut Rng, difficulty: u32) -> Option<Level> {
+    let width = 24 + difficulty * 6;
+    let height = 16 + difficulty * 4;
+    let mut layout = Layout::new(width, height);
+
+    let room_count = 2 + difficulty;
+    let mut rooms: Vec<Room> = Vec::new();
+    for _ in 0..room_count {
+        let room_width = rng.range(6, 12);
+        let room_height = rng.range(5, 9);
+        let room = Room {
+            x: rng.range(1, width - room_width - 1),
+            y: rng.range(1, height - room_height - 1),
+            width: room_width,
+            height: room_height,
+        };
+        layout.fill(room.x, room.y, room.width, room.height, true);
+
+        // Join every room to the previous one with an L shaped corridor.
+        if let Some(previous) = rooms.last() {
+            let (x1, y1) = previous.center();
+            let (x2, y2) = room.center();
+            let size = rng.range(2, 4);
+            let corner = if rng.next_u32() & 1 == 0 { (x2, y1) } else { (x1, y2) };
+            for (from, to) in [((x1, y1), corner), (corner, (x2, y2))] {
+                let x = from.0.min(to.0);
+                let y = from.1.min(to.1);
+                layout.fill(
+                    x,
+                    y,
+                    from.0.abs_diff(to.0) + size,
+                    from.1.abs_diff(to.1) + size,
+                    true,
+                );
+            }
+        }
+        rooms.push(room);
+    }
+
+    // Pillars, only where they leave a gap of at least two tiles all around.
+    for _ in 0..difficulty * 2 {
+        let x = rng.range(3, width - 5);
+        let y = rng.range(3, height - 5);
+        let clear = (y - 2..y + 4).all(|row| {
+            (x - 2..x + 4).all(|column| layout.is_open(column as i32, row as i32))
+        });
+        if clear {
+            layout.fill(x, y, 2, 2, false);
+        }
+    }
+
+    let first = &rooms[0];
+    let last = &rooms[rooms.len() - 1];
+    let player = pick_clear(rng, &layout, first)?;
+    let goal = pick_clear(rng, &layout, last)?;
+    if player.0.abs_diff(goal.0) + player.1.abs_diff(goal.1) < 8 {
+        return None;
+    }
+    Some(layout.to_level(player, goal))
+}
+
+fn pick_clear(rng: &mut Rng, layout: &Layout, room: &Room) -> Option<(u32, u32)> {
+    (0..8)
+        .map(|_| {
+            (
+                rng.range(room.x, room.x + room.width),
+                rng.range(room.y, room.y + room.height),
+            )
+        })
+        .find(|&(x, y)| layout.is_clear(x, y))
+}
+
+/// A plain room, in case no layout could be built.
+fn fallback() -> Level {
+    let mut layout = Layout::new(20, 12);
+    layout.fill(1, 1, 18, 10, true);
+    layout.to_level((4, 6), (15, 6))
+}
@@ -1,3 +1,7 @@
+use crate::editor;
+use crate::game::{Level, Tile};
+use crate::theme::{self, Theme};
+
 const LEVEL_1: &str = 
 "
 l-----------r
@@ -117,4 +121,628 @@ const LEVEL_6: &str =
                                    --------";
 
 
-pub const LEVELS : [&str; 6] = [LEVEL_1, LEVEL_2, LEVEL_3, LEVEL_4, LEVEL_5, LEVEL_6];
\ No newline at end of file
+const BEGINNER_1: &str =
+"
+l-----------------r
+|                 |
+|        x        |
+|                 |
+|                 |
+|                 |
+|                 |
+|                 |
+|        p        |
+|                 |
+L-----------------R";
+
+const BEGINNER_2: &str =
+"
+l-------------------r
+|                   |
+|                   |
+|  p      |      x  |
+|         |         |
+|                   |
+L-------------------R";
+
+const BEGINNER_3: &str =
+"
+l-------------r
+|             |
+|      x      |
+|             |
+|             |
+|    l---r    |
+|    |   |    |
+|    L---R    |
+|             |
+|             |
+|      p      |
+|             |
+L-------------R";
+
+const BEGINNER_4: &str =
+"
+l-------------------r
+|                   |
+|  p                |
+|                   |
+|             l-----R
+|             |      
+|             |      
+|             |      
+|             |      
+|  x          |      
+|             |      
+L-------------R      ";
+
+const BEGINNER_5: &str =
+"
+l-----------------r
+|      |          |
+|  x   |          |
+|      |    l     |
+|      |    |  p  |
+|      L    |     |
+|           |     |
+L-----------------R";
+
+const BEGINNER_6: &str =
+"
+l-----------r
+|           |
+|  x        |
+|           |
+|-------r   |
+|       |   |
+|       |   |
+|       |   |
+|-------R   |
+|           |
+|        p  |
+|           |
+L-----------R";
+
+const BEGINNER_7: &str =
+"
+l-------------------r
+|                   |
+|                   |
+|   l---r   l---r   |
+| p |   |   |   | x |
+|   L---R   L---R   |
+|                   |
+|                   |
+L-------------------R";
+
+const BEGINNER_8: &str =
+"
+l-------------------r
+|                   |
+|                   |
+|     l-------r     |
+|     |       |     |
+|  p  |       |  x  |
+|     |       |     |
+L-----R       L-----R";
+
+const BEGINNER_9: &str =
+"
+l-----------------------r
+|                       |
+|  x                    |
+|                       |
+L-----------r           |
+            |           |
+            |           |
+l-----------R           |
+|                       |
+|                    p  |
+|                       |
+L-----------------------R";
+
+const HARD_1: &str =
+"
+l------------------------------r
+|                              |
+|  p                           |
+|                              |
+L-------------------r          |
+l-------------------R          |
+|                              |
+|                              |
+|                              |
+|          l-------------------R
+|          L-------------------r
+|                              |
+|                          x   |
+|                              |
+L------------------------------R";
+
+const HARD_2: &str =
+"
+l----------------l--------------r
+|                |              |
+|                |              |
+|   p            L              |
+|                           x   |
+|                l              |
+|                |              |
+|                |              |
+L----------------L--------------R";
+
+const HARD_3: &str =
+"
+l------------------------r
+|                        |
+|  p                     |
+|                        |
+|                        |
+|        l----r    l-----R
+|        |    |    |      
+|        |  x |    |      
+|        |    |    |      
+|        L- --R    |      
+|                  |      
+|                  |      
+L------------------R      ";
+
+const HARD_4: &str =
+"
+l------------------r
+|                  |
+|  p               |
+|                  |
+L------r  l--------R
+l------R  L--------r
+|                  |
+|                  |
+L--r  l------------R
+l--R  L------------r
+|                  |
+|                  |
+L----------r  l----R
+l----------R  L----r
+|                  |
+|            x     |
+|                  |
+L------------------R";
+
+const HARD_5: &str =
+"
+l----------------------------------r
+|                                  |
+|  p    l       l        l         |
+|       |   l   |   l    |    l    |
+|       L   |   L   |    L    |    |
+|           L       L         L  x |
+|                                  |
+L----------------------------------R";
+
+const HARD_6: &str =
+"
+l--------------------------------r
+|                                |
+|  p                             |
+|                                |
+|       lr       lr       lr     |
+|       LR       LR       LR     |
+|                                |
+|    lr       lr       lr        |
+|    LR       LR       LR        |
+|                                |
+|       lr       lr       lr     |
+|       LR       LR       LR  x  |
+|                                |
+L--------------------------------R";
+
+const HARD_7: &str =
+"
+l----------------------------------------r
+|                                        |
+|  p                                     |
+|                                        |
+L---------------------------------r      |
+l---------------------------------R      |
+|                                        |
+|                                        |
+|                                        |
+|      l---------------------------------R
+|      L---------------------------------r
+|                                        |
+|                                        |
+|                                        |
+L---------------------------------r      |
+l---------------------------------R      |
+|                                        |
+|                                        |
+|                                        |
+|      l---------------------------------R
+|      L---------------------------------r
+|                                        |
+|                                   x    |
+|                                        |
+L----------------------------------------R";
+
+const HARD_8: &str =
+"
+l-----------------------------r
+|                             |
+|                             |
+|   p                         |
+|                             |
+|       l-------------r       |
+|       |             |       |
+|       |     l--r    |       |
+|       |     |  |    |       |
+|       |     |x |    |       |
+|       |     |  |    |       |
+|       L-----R ------R       |
+|                             |
+|                             |
+L-----------------------------R";
+
+const HARD_9: &str =
+"
+l------------------------------------------------------------------------------------------------------------------------------r
+|                                                                                                                              |
+|  p                                                                                                                       x   |
+|                                                                                                                              |
+L------------------------------------------------------------------------------------------------------------------------------R";
+
+const HARD_10: &str =
+"
+l-----------r
+|           |
+|  p        |
+|           |
+|           |
+L------r    |
+l------R    |
+|           |
+|           |
+|    l------R
+|    L------r
+|           |
+|           |
+L------r    |
+l------R    |
+|           |
+|           |
+|    l------R
+|    L------r
+|           |
+|   x       |
+|           |
+L-----------R";
+
+const HARD_11: &str =
+"
+l-------------------l----------r 
+|                   |          | 
+|  p                |          | 
+|                   |          | 
+|           l---    L    ----  | 
+|           |                l | 
+|           |  x             | | 
+|           |                | Lr
+|           L----------------R  |
+|                              lR
+|                              | 
+L------------------------------R ";
+
+const HARD_12: &str =
+"
+l--------------------------------------------r
+|                                            |
+|  p                                         |
+|                                            |
+L-------------------------------------r      |
+l-------------------------------------R      |
+|                                            |
+|                                            |
+|                                            |
+|      l-------------------------------------R
+|      L-------------------------------------r
+|                                            |
+|                                            |
+|                                            |
+L-------------------------------------r      |
+l-------------------------------------R      |
+|                                            |
+|  x                                         |
+|                                            |
+L--------------------------------------------R";
+
+const HARD_13: &str =
+"
+l--------------r
+|              |
+|  p           |
+|              |
+L-----------r  |
+l-----------R  |
+|              |
+|              |
+|  l-----------R
+|  L-----------r
+|              |
+|              |
+L-----------r  |
+l-----------R  |
+|              |
+|          x   |
+|              |
+L--------------R";
+
+const HARD_14: &str =
+"
+l--------------------r
+|                    |
+|  p                 |
+|                    |
+|      l---r         |
+|      |   |         |
+|      | x |         |
+|      |   L         |
+|      |             |
+|      |   l         |
+|      L---R         |
+|                    |
+L--------------------R";
+
+const HARD_15: &str =
+"
+l----------------------------------------r
+|                                        |
+|  p         l--------r                  |
+|            |        |                  |
+|            |        |       l----      |
+|            |    x   |       |          |
+|            |        |       |          |
+|            L-----   |       L          |
+|                     |                  |
+|                     |                  |
+L---------------------L------------------R";
+
+const HARD_16: &str =
+"
+l----------------------------------r
+|                                  |
+|  p                               |
+|                                  |
+|        l          l         l    |
+|        |    l-r   |   l-r   |    |
+|        |    | L   |   Lx|   |    |
+|        L    |     L     L   L    |
+|             |                    |
+L-------------L--------------------R";
+
+const HARD_17: &str =
+"
+l--------------------r
+|                    |
+|      x             |
+|                    |
+|                    |
+|    l---------r     |
+|    |         |     |
+|    |    p    |     |
+|    |         |     |
+|    L-----  --R     |
+|                    |
+L--------------------R";
+
+const HARD_18: &str =
+"
+l---rl--------rl--------r
+|   ||        ||        |
+| p ||        ||      x |
+|   ||        ||        |
+|   ||        ||        |
+|   ||   lr   ||   lr   |
+|   ||   ||   ||   ||   |
+|   ||   ||   ||   ||   |
+|   ||   ||   ||   ||   |
+|   ||   ||   ||   ||   |
+|   ||   ||   ||   ||   |
+|   ||   ||   ||   ||   |
+|   ||   ||   ||   ||   |
+|   ||   ||   ||   ||   |
+|   ||   ||   ||   ||   |
+|   ||   ||   ||   ||   |
+|   ||   ||   ||   ||   |
+|   ||   ||   ||   ||   |
+|   ||   ||   ||   ||   |
+|   ||   ||   ||   ||   |
+|   ||   ||   ||   ||   |
+|   ||   ||   ||   ||   |
+|   ||   ||   ||   ||   |
+|   ||   ||   ||   ||   |
+|   ||   ||   ||   ||   |
+|   ||   ||   ||   ||   |
+|   ||   ||   ||   ||   |
+|   ||   ||   ||   ||   |
+|   ||   ||   ||   ||   |
+|   ||   ||   ||   ||   |
+|   LR   ||   LR   ||   |
+|        ||        ||   |
+|        ||        ||   |
+|        ||        ||   |
+|        ||        ||   |
+L--------RL--------RL---R";
+
+pub struct Hole {
+    pub name: &'static str,
+    pub par: u8,
+    /// The map as runs of tiles, see `editor::encode`.
+    pub map: &'static [u8],
+}
+
+impl Hole {
+    pub fn level(&self) -> Level {
+        editor::decode(self.map).unwrap()
+    }
+}
+
+/// Run length encodes a map written as text while the cart is built, so only
+/// the runs take up room in it.
+macro_rules! map {
+    ($text:expr) => {{
+        const RUNS: [u8; $crate::levels::encode_into($text, &mut [])] =
+            $crate::levels::encode($text);
+        &RUNS
+    }};
+}
+pub(crate) use map;
+
+pub(crate) const fn encode<const N: usize>(text: &str) -> [u8; N] {
+    let mut runs = [0; N];
+    encode_into(text, &mut runs);
+    runs
+}
+
+/// Writes the runs of a map written as text the way `editor::encode` does, as
+/// far as `runs` has room, and returns how many bytes they take. Blank lines
+/// are skipped like `Level::from_str` does.
+pub(crate) const fn encode_into(text: &str, runs: &mut [u8]) -> usize {
+    let text = text.as_bytes();
+    let (mut width, mut height, mut column) = (0usize, 0usize, 0);
+    let mut length = 2;
+    // Tile index and length of the run being counted.
+    let (mut tile, mut run) = (0, 0);
+    let mut i = 0;
+    while i <= text.len() {
+        if i == text.len() || text[i] == b'\n' {
+            if column > 0 {
+                if width == 0 {
+                    width = column;
+                }
+                assert!(column == width, "the rows of a map differ in width");
+                height += 1;
+            }
+            column = 0;
+        } else {
+            let next = tile_index(text[i]);
+            column += 1;
+            if run > 0 && (next != tile || run == 16) {
+                if length < runs.len() {
+                    runs[length] = tile | ((run - 1) << 4);
+                }
+                length += 1;
+                run = 0;
+            }
+            tile = next;
+            run += 1;
+        }
+        i += 1;
+    }
+    if run > 0 {
+        if length < runs.len() {
+            runs[length] = tile | ((run - 1) << 4);
+        }
+        length += 1;
+    }
+    assert!(width <= u8::MAX as usize && height <= u8::MAX as usize, "map too big");
+    if runs.len() >= 2 {
+        runs[0] = width as u8;
+        runs[1] = height as u8;
+    }
+    length
+}
+
+/// Index in `Tile::ALL` of the tile written as `symbol`.
+const fn tile_index(symbol: u8) -> u8 {
+    let mut i = 0;
+    while i < Tile::ALL.len() {
+        if Tile::ALL[i].symbol() as u32 == symbol as u32 {
+            return i as u8;
+        }
+        i += 1;
+    }
+    panic!("unknown tile in a map");
+}
+
+pub struct Course {
+    pub name: &'static str,
+    pub holes: &'static [Hole],
+    pub theme: &'static Theme,
+}
+
+impl Course {
+    pub fn par(&self) -> u16 {
+        self.holes.iter().map(|hole| hole.par as u16).sum()
+    }
+}
+
+const BEGINNER: [Hole; 9] = [
+    Hole { name: "First Putt", par: 2, map: map!(BEGINNER_1) },
+    Hole { name: "Side Step", par: 2, map: map!(BEGINNER_2) },
+    Hole { name: "The Post", par: 2, map: map!(BEGINNER_3) },
+    Hole { name: "Elbow", par: 2, map: map!(BEGINNER_4) },
+    Hole { name: "Slalom", par: 3, map: map!(BEGINNER_5) },
+    Hole { name: "Hook", par: 3, map: map!(BEGINNER_6) },
+    Hole { name: "Twin Posts", par: 2, map: map!(BEGINNER_7) },
+    Hole { name: "Over the Top", par: 3, map: map!(BEGINNER_8) },
+    Hole { name: "Zig Zag", par: 3, map: map!(BEGINNER_9) },
+];
+
+const CLASSIC: [Hole; 6] = [
+    Hole { name: "Warm Up", par: 2, map: map!(LEVEL_1) },
+    Hole { name: "Dogleg", par: 2, map: map!(LEVEL_2) },
+    Hole { name: "The Block", par: 3, map: map!(LEVEL_3) },
+    Hole { name: "Straight Shot", par: 2, map: map!(LEVEL_4) },
+    Hole { name: "Detour", par: 3, map: map!(LEVEL_5) },
+    Hole { name: "Spiral", par: 3, map: map!(LEVEL_6) },
+];
+
+const HARD: [Hole; 18] = [
+    Hole { name: "Switchback", par: 3, map: map!(HARD_1) },
+    Hole { name: "Narrows", par: 3, map: map!(HARD_2) },
+    Hole { name: "Keyhole", par: 3, map: map!(HARD_3) },
+    Hole { name: "Chicane", par: 3, map: map!(HARD_4) },
+    Hole { name: "Fence Line", par: 3, map: map!(HARD_5) },
+    Hole { name: "Pinball", par: 3, map: map!(HARD_6) },
+    Hole { name: "Serpent", par: 4, map: map!(HARD_7) },
+    Hole { name: "Hidden Cup", par: 3, map: map!(HARD_8) },
+    Hole { name: "Long Drive", par: 3, map: map!(HARD_9) },
+    Hole { name: "Ladder", par: 3, map: map!(HARD_10) },
+    Hole { name: "Dead End", par: 3, map: map!(HARD_11) },
+    Hole { name: "Hairpins", par: 4, map: map!(HARD_12) },
+    Hole { name: "Stairwell", par: 3, map: map!(HARD_13) },
+    Hole { name: "Side Door", par: 3, map: map!(HARD_14) },
+    Hole { name: "Back Room", par: 3, map: map!(HARD_15) },
+    Hole { name: "Pockets", par: 3, map: map!(HARD_16) },
+    Hole { name: "Breakout", par: 3, map: map!(HARD_17) },
+    Hole { name: "Labyrinth", par: 4, map: map!(HARD_18) },
+];
+
+pub const COURSES: [Course; 3] = [
+    Course {
+        name: "Beginner",
+        holes: &BEGINNER,
+        theme: &theme::MEADOW,
+    },
+    Course {
+        name: "Classic",
+        holes: &CLASSIC,
+        theme: &theme::HARBOUR,
+    },
+    Course {
+        name: "Hard",
+        holes: &HARD,
+        theme: &theme::CANYON,
+    },
+];
+
+/// Holes across all courses, used to lay out per hole records on disk.
+pub const HOLE_COUNT: usize = BEGINNER.len() + CLASSIC.len() + HARD.len();
+
+/// Position of a course's hole among all holes.
+pub fn hole_index(course: usize, hole: usize) -> usize {
+    COURSES[..course]
+        .iter()
+        .map(|course| course.holes.len())
+        .sum::<usize>()
+        + hole
+}
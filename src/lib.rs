@@ -1,11 +1,30 @@
 #[cfg(feature = "buddy-alloc")]
+#[allow(static_mut_refs)]
 mod alloc;
-mod game;
-mod levels;
+pub mod achievements;
+pub mod audio;
+mod camera;
+pub mod daily;
+mod editor;
+pub mod effects;
+pub mod events;
+pub mod game;
+mod ghost;
+pub mod levelgen;
+pub mod levels;
+mod menu;
+pub mod replay;
+mod save;
+mod sprites;
+pub mod stats;
+pub mod theme;
+mod transition;
+#[allow(clippy::manual_dangling_ptr)]
 mod wasm4;
 use std::sync::Mutex;
 
-use libm::sqrtf;
+use achievements::Achievement;
+use theme::Role;
 use wasm4::*;
 
 pub const BALL_SIZE: u32 = 1;
@@ -17,18 +36,66 @@ pub const PUSH_FORCE: f32 = 0.013;
 pub const MAX_SPEED: f32 = 2.5;
 
 static PREVIOUS_MOUSE_BUTTON: Mutex<bool> = Mutex::new(false);
+/// Where the mouse button last went down.
+static PRESS_POSITION: Mutex<(i16, i16)> = Mutex::new((0, 0));
 static PREVIOUS_GAMEPAD_X: Mutex<u8> = Mutex::new(0);
+/// Whether the shot being aimed was called off, so letting go doesn't shoot.
+static AIM_CANCELLED: Mutex<bool> = Mutex::new(false);
+/// Frames since the cart started, used to seed the endless mode.
+static TICKS: Mutex<u32> = Mutex::new(0);
+static PAUSE_MENU: Mutex<menu::Menu> = Mutex::new(menu::Menu::new());
+static COURSE_MENU: Mutex<menu::Menu> = Mutex::new(menu::Menu::new());
+/// Shows 9 holes at a time, leaving room for the selected one's map below.
+static LEVEL_MENU: Mutex<menu::Menu> = Mutex::new(menu::Menu::scrolling(9));
+static DATE_ENTRY: Mutex<daily::DateEntry> = Mutex::new(daily::DateEntry::new());
+static EDITOR: Mutex<editor::Editor> = Mutex::new(editor::Editor::new());
+
+/// What picking an item of the title menu does.
+#[derive(Clone, Copy)]
+enum TitleItem {
+    /// Plays the course at this index of `levels::COURSES`.
+    Course(usize),
+    Endless,
+    Daily,
+    Editor,
+    Theme,
+}
+
+/// What picking an item of the pause menu does.
+#[derive(Clone, Copy)]
+enum PauseItem {
+    Resume,
+    AimAssist,
+    Effects,
+    Theme,
+    Mulligan,
+    RestartHole,
+    RestartRound,
+    BackToEditor,
+    Quit,
+}
+
+/// Size of the clickable pause button in the top left corner.
+const PAUSE_BUTTON_SIZE: i16 = 10;
+
+/// Whether `x`, `y` is on the pause button.
+fn on_pause_button(x: i16, y: i16) -> bool {
+    x < PAUSE_BUTTON_SIZE && y < PAUSE_BUTTON_SIZE
+}
+/// Letting go this close to the ball, in pixels, doesn't shoot.
+const CANCEL_RADIUS: i16 = 4;
 
 lazy_static::lazy_static! {
     static ref GAME: Mutex<game::Game> = Mutex::new(game::Game::new());
 }
 
+/// Whether `button` was let go of this frame.
+fn released(gamepad: u8, button: u8) -> bool {
+    gamepad & button == 0 && *PREVIOUS_GAMEPAD_X.lock().unwrap() & button != 0
+}
+
 #[no_mangle]
 fn start() {
-    unsafe {
-        *PALETTE = [0x00303b, 0xff7777, 0xffce96, 0xf1f2da];
-    }
-
     GAME.lock().unwrap().initialize_ball();
 }
 
@@ -37,14 +104,198 @@ fn update() {
     let mut game = GAME.lock().unwrap();
     let mouse = unsafe { *MOUSE_BUTTONS };
     let gamepad = unsafe { *GAMEPAD1 };
+    let previous_state = game.state;
+    // Events queued outside of play, like a new hole, reach the observers
+    // before anything is drawn.
+    game.dispatch_events();
 
     match game.state {
         game::State::Menu => {
-            text("Press Space or X\n     to Start", 10, 80);
+            theme::set_color(Role::Title);
+            text("Minigolf", 48, 10);
+            theme::set_color(Role::Text);
+            text("Choose a course", 20, 30);
+
+            let theme = format!("Theme: {}", game.theme_name());
+            let mut items: Vec<(&str, TitleItem)> = levels::COURSES
+                .iter()
+                .enumerate()
+                .map(|(i, course)| (course.name, TitleItem::Course(i)))
+                .collect();
+            items.push(("Endless", TitleItem::Endless));
+            items.push(("Daily hole", TitleItem::Daily));
+            items.push(("Level editor", TitleItem::Editor));
+            items.push((&theme, TitleItem::Theme));
+            let labels: Vec<&str> = items.iter().map(|(label, _)| *label).collect();
+            let mut menu = COURSE_MENU.lock().unwrap();
+            let chosen = menu.update(&labels, 20, 40);
+
+            theme::set_color(Role::Highlight);
+            match items[menu.selected].1 {
+                TitleItem::Course(i) => {
+                    let course = &levels::COURSES[i];
+                    text(
+                        format!("{} holes  Par {}", course.holes.len(), course.par()),
+                        8,
+                        114,
+                    );
+                    match game.save.courses[i] {
+                        0 => text("Best: -", 8, 122),
+                        best => text(format!("Best: {}", best), 8, 122),
+                    }
+                }
+                TitleItem::Endless => text("New holes forever", 8, 114),
+                TitleItem::Daily => text("One try a day", 8, 114),
+                TitleItem::Editor => text("Build your own hole", 8, 114),
+                TitleItem::Theme => text("Colours of the game", 8, 114),
+            }
+            text(format!("< {} >", game.difficulty.name()), 8, 136);
+            text(format!("Mulligans: {}", game.difficulty.mulligans()), 8, 146);
+
+            match chosen.map(|index| items[index].1) {
+                Some(TitleItem::Course(course)) => {
+                    game.set_course(course as u16);
+                    game.state = game::State::HoleSelect;
+                    LEVEL_MENU.lock().unwrap().open();
+                }
+                Some(TitleItem::Endless) => {
+                    let seed = *TICKS.lock().unwrap();
+                    trace(format!("Endless seed: {}", seed));
+                    game.set_endless(seed);
+                    game.state = game::State::HoleIntro;
+                    game.restart_round();
+                }
+                Some(TitleItem::Daily) => {
+                    game.state = game::State::DailyEntry;
+                    let date = daily::Date::from_u32(game.save.daily_date);
+                    DATE_ENTRY.lock().unwrap().open(date);
+                }
+                Some(TitleItem::Editor) => {
+                    game.state = game::State::Editor;
+                    EDITOR.lock().unwrap().open(&game.save);
+                }
+                Some(TitleItem::Theme) => game.cycle_theme(),
+                None => {}
+            }
+            if released(gamepad, BUTTON_LEFT) {
+                game.difficulty = game.difficulty.previous();
+            } else if released(gamepad, BUTTON_RIGHT) {
+                game.difficulty = game.difficulty.next();
+            }
+        }
+        game::State::HoleSelect => {
+            let course = &levels::COURSES[game.course as usize];
+            theme::set_color(Role::Title);
+            text(course.name, 0, 4);
 
-            if gamepad & BUTTON_1 == 0 && *PREVIOUS_GAMEPAD_X.lock().unwrap() & BUTTON_1 != 0 {
-                game.state = game::State::Playing;
-                game.initialize_ball();
+            let mut items: Vec<String> = course
+                .holes
+                .iter()
+                .enumerate()
+                .map(|(i, hole)| {
+                    let best = game.record(i as u16).map_or(0, |record| record.best);
+                    // Pad the names so the scores line up at the right edge.
+                    let width = if i + 1 < 10 { 13 } else { 12 };
+                    if !game.is_unlocked(i as u16) {
+                        format!("{} Locked", i + 1)
+                    } else if best == 0 {
+                        format!("{} {:<width$} -/{}", i + 1, hole.name, hole.par)
+                    } else {
+                        format!("{} {:<width$}{:>2}/{}", i + 1, hole.name, best, hole.par)
+                    }
+                })
+                .collect();
+            items.push(String::from("Back"));
+            let items: Vec<&str> = items.iter().map(String::as_str).collect();
+            let mut menu = LEVEL_MENU.lock().unwrap();
+            let chosen = menu.update(&items, 0, 16);
+
+            let selected = menu.selected;
+            if selected < game.levels.len() {
+                game.load_level(selected as u16);
+            }
+            if let Some(level) = game.levels.get(selected) {
+                if game.is_unlocked(selected as u16) {
+                    let top = 16 + items.len().min(menu.rows) as u16 * 10;
+                    let scale = (150 / level.width).min((158 - top) / level.rows()).max(1);
+                    level.draw_map(
+                        80 - (level.width * scale) as i32 / 2,
+                        top as i32,
+                        scale as f32,
+                        true,
+                    );
+                } else {
+                    theme::set_color(Role::Text);
+                    text("Clear the previous\nhole to unlock it", 8, 130);
+                }
+            }
+
+            // In debug builds holding Z skips the lock so holes can be tested out
+            // of order.
+            match chosen {
+                Some(level) if level == game.levels.len() => {
+                    game.state = game::State::Menu;
+                    COURSE_MENU.lock().unwrap().open();
+                }
+                Some(level)
+                    if game.is_unlocked(level as u16)
+                        || cfg!(debug_assertions) && gamepad & BUTTON_2 != 0 =>
+                {
+                    game.start_level = level as u16;
+                    game.state = game::State::HoleIntro;
+                    game.restart_round();
+                }
+                _ => {}
+            }
+        }
+        game::State::DailyEntry => {
+            theme::set_color(Role::Title);
+            text("Daily hole", 40, 10);
+            theme::set_color(Role::Text);
+            text("Enter today's date", 8, 40);
+            text("Arrows: change\nX: play  Z: back", 8, 120);
+
+            let mut entry = DATE_ENTRY.lock().unwrap();
+            let confirmed = entry.update(40, 60);
+            let date = entry.date;
+            let played = game.save.daily_date == date.to_u32();
+            let playable = daily::is_playable(date, game.save.daily_date);
+            theme::set_color(Role::Highlight);
+            if played && game.save.daily_score == 0 {
+                text("Already tried,\nnot finished", 8, 85);
+            } else if played {
+                text(format!("Already played\nScore: {}", game.save.daily_score), 8, 85);
+            } else if !playable {
+                text("A later day was\nalready played", 8, 85);
+            }
+
+            match confirmed {
+                Some(true) if playable => {
+                    game.set_daily(date);
+                    game.state = game::State::HoleIntro;
+                    game.restart_round();
+                }
+                Some(false) => {
+                    game.state = game::State::Menu;
+                    COURSE_MENU.lock().unwrap().open();
+                }
+                _ => {}
+            }
+        }
+        game::State::Editor => {
+            let mut editor = EDITOR.lock().unwrap();
+            match editor.update(&mut game.save) {
+                editor::Action::TestPlay => {
+                    game.set_custom(editor.level.clone());
+                    game.state = game::State::HoleIntro;
+                    game.restart_round();
+                }
+                editor::Action::Exit => {
+                    game.set_course(0);
+                    game.state = game::State::Menu;
+                    COURSE_MENU.lock().unwrap().open();
+                }
+                editor::Action::None => {}
             }
         }
         game::State::Playing => {
@@ -57,57 +308,206 @@ fn update() {
             game.update();
             game.draw();
 
-            if mouse & MOUSE_LEFT != 0 {
-                if game.is_stationary() {
-                    unsafe { *DRAW_COLORS = 4 }
+            // Shots are aimed from the ball, which the camera doesn't always keep centred.
+            let (ball_x, ball_y) = game.camera.screen_position(game.position);
+
+            if game.playback.is_some() {
+                theme::set_color(Role::Title);
+                text("Replay", 0, 0);
+                game.play_recorded_shot();
+
+                if released(gamepad, BUTTON_1) {
+                    game.stop_playback();
+                } else if released(gamepad, BUTTON_LEFT) {
+                    let level = game.level.saturating_sub(1).max(game.start_level);
+                    game.start_playback(level);
+                } else if released(gamepad, BUTTON_RIGHT) {
+                    let level = (game.level + 1).min(game.levels.len() as u16 - 1);
+                    game.start_playback(level);
+                }
+            } else if released(gamepad, BUTTON_1) && !game.effects.sinking() {
+                if gamepad & BUTTON_2 != 0 {
+                    // Holding Z and pressing X takes a mulligan without the pause menu.
+                    game.mulligan();
                 } else {
-                    unsafe { *DRAW_COLORS = 2 }
-                }
-                line(
-                    mouse_x as i32,
-                    mouse_y as i32,
-                    SCREEN_SIZE as i32 / 2,
-                    SCREEN_SIZE as i32 / 2,
-                );
-            } else if *PREVIOUS_MOUSE_BUTTON.lock().unwrap() != mouse_left {
-                if game.is_stationary() {
-                    let push_x = -(mouse_x - SCREEN_SIZE as i16 / 2) as f32 * PUSH_FORCE;
-                    let push_y = (mouse_y - SCREEN_SIZE as i16 / 2) as f32 * PUSH_FORCE;
-                    let speed = sqrtf(push_x * push_x + push_y * push_y);
-                    if speed < MAX_SPEED {
-                        game.velocity.x = push_x;
-                        game.velocity.y = push_y;
+                    game.state = game::State::Paused;
+                    PAUSE_MENU.lock().unwrap().open();
+                }
+            } else if mouse & MOUSE_LEFT != 0 {
+                if !*PREVIOUS_MOUSE_BUTTON.lock().unwrap() {
+                    *PRESS_POSITION.lock().unwrap() = (mouse_x, mouse_y);
+                }
+                let mut cancelled = AIM_CANCELLED.lock().unwrap();
+                if mouse_right || gamepad & BUTTON_2 != 0 {
+                    *cancelled = true;
+                }
+                game.camera.panning = false;
+                let drag_x = mouse_x - ball_x as i16;
+                let drag_y = mouse_y - ball_y as i16;
+                let near_ball = drag_x.abs() <= CANCEL_RADIUS && drag_y.abs() <= CANCEL_RADIUS;
+
+                if *cancelled {
+                    theme::set_color(Role::Highlight);
+                    text("Cancelled", 44, 16);
+                } else if !near_ball {
+                    if game.is_stationary() {
+                        if game.shows_aim_assist() {
+                            game.draw_aim_assist(drag_x, drag_y);
+                        }
+                        game.draw_power_meter(drag_x, drag_y);
+                        theme::set_color(Role::Title);
                     } else {
-                        game.velocity.x = push_x * MAX_SPEED / speed;
-                        game.velocity.y = push_y * MAX_SPEED / speed;
+                        theme::set_color(Role::Text);
                     }
-                    game.score += 1;
+                    line(mouse_x as i32, mouse_y as i32, ball_x, ball_y);
                 }
-            } else if mouse_right || gamepad & BUTTON_2 != 0{
-                game.scale = OVERVIEW_SCALE;
-            } else if !mouse_right && gamepad & BUTTON_2 == 0 {
-                game.scale = SCALE;
+            } else if *PREVIOUS_MOUSE_BUTTON.lock().unwrap() != mouse_left {
+                let cancelled = std::mem::take(&mut *AIM_CANCELLED.lock().unwrap());
+                let drag_x = mouse_x - ball_x as i16;
+                let drag_y = mouse_y - ball_y as i16;
+                let near_ball = drag_x.abs() <= CANCEL_RADIUS && drag_y.abs() <= CANCEL_RADIUS;
+
+                // Only a click on the button pauses, not a drag that ends there.
+                // The hole is already scored while the ball drops in.
+                let (press_x, press_y) = *PRESS_POSITION.lock().unwrap();
+                if on_pause_button(press_x, press_y)
+                    && on_pause_button(mouse_x, mouse_y)
+                    && !game.effects.sinking()
+                {
+                    game.state = game::State::Paused;
+                    PAUSE_MENU.lock().unwrap().open();
+                } else if game.is_stationary() && !cancelled && !near_ball {
+                    game.shoot(drag_x, drag_y);
+                }
+            } else {
+                game.camera.overview = mouse_right || gamepad & BUTTON_2 != 0;
             }
             *PREVIOUS_MOUSE_BUTTON.lock().unwrap() = mouse_left;
 
+            // The arrows look around the hole before a shot.
+            if game.playback.is_none() && !mouse_left && game.is_stationary() {
+                let axis = |negative, positive| {
+                    (gamepad & positive != 0) as i8 as f32 - (gamepad & negative != 0) as i8 as f32
+                };
+                let x = axis(BUTTON_LEFT, BUTTON_RIGHT);
+                let y = axis(BUTTON_DOWN, BUTTON_UP);
+                if x != 0.0 || y != 0.0 {
+                    game.pan_camera(x, y);
+                }
+            }
+
+            if game.playback.is_none() && !game.effects.sinking() {
+                theme::set_draw_colors(Some(Role::Highlight), Some(Role::Highlight));
+                rect(2, 2, 2, 6);
+                rect(6, 2, 2, 6);
+            }
+        }
+        game::State::HoleIntro => game.update_intro(released(gamepad, BUTTON_1)),
+        game::State::HoleOutro => game.update_outro(released(gamepad, BUTTON_1)),
+        game::State::Paused => {
+            game.draw();
+
+            theme::set_draw_colors(Some(Role::Background), Some(Role::Text));
+            rect(16, 40, 128, 90);
+            let mulligan = format!("Mulligan ({})", game.mulligans);
+            let aim_assist = if game.aim_assist {
+                "Aim assist: on"
+            } else {
+                "Aim assist: off"
+            };
+            let effects = if game.effects.enabled {
+                "Effects: on"
+            } else {
+                "Effects: off"
+            };
+            let theme = format!("Theme: {}", game.theme_name());
+            let mut items = vec![("Resume", PauseItem::Resume)];
+            if game.difficulty.allows_aim_assist() {
+                items.push((aim_assist, PauseItem::AimAssist));
+            }
+            items.push((effects, PauseItem::Effects));
+            items.push((&theme, PauseItem::Theme));
+            // The daily hole only gets one attempt, so it can't be restarted.
+            if game.daily.is_none() {
+                items.push((&mulligan, PauseItem::Mulligan));
+                items.push(("Restart hole", PauseItem::RestartHole));
+            }
+            if game.custom {
+                items.push(("Back to editor", PauseItem::BackToEditor));
+            } else {
+                if game.daily.is_none() {
+                    items.push(("Restart round", PauseItem::RestartRound));
+                }
+                items.push(("Quit to title", PauseItem::Quit));
+            }
+            let labels: Vec<&str> = items.iter().map(|(label, _)| *label).collect();
+            let chosen = PAUSE_MENU.lock().unwrap().update(&labels, 24, 46);
+            match chosen.map(|index| items[index].1) {
+                Some(PauseItem::Resume) => game.state = game::State::Playing,
+                Some(PauseItem::RestartHole) => {
+                    game.restart_hole();
+                    game.state = game::State::Playing;
+                }
+                Some(PauseItem::RestartRound) => {
+                    game.restart_round();
+                    game.state = game::State::HoleIntro;
+                }
+                Some(PauseItem::BackToEditor) => game.state = game::State::Editor,
+                Some(PauseItem::AimAssist) => game.aim_assist = !game.aim_assist,
+                Some(PauseItem::Theme) => game.cycle_theme(),
+                Some(PauseItem::Effects) => game.effects.enabled = !game.effects.enabled,
+                Some(PauseItem::Quit) => {
+                    game.restart_round();
+                    game.state = game::State::Menu;
+                    COURSE_MENU.lock().unwrap().open();
+                }
+                Some(PauseItem::Mulligan) => {
+                    game.mulligan();
+                    game.state = game::State::Playing;
+                }
+                None => {}
+            }
         }
         game::State::GameOver => {
-            unsafe { *DRAW_COLORS = 3 }
+            theme::set_color(Role::Highlight);
             text("Congratulations!", 10, 50);
             text(format!("Your score is {}", game.score), 10, 70);
-            unsafe { *DRAW_COLORS = 2 }
-            text("Press Space or X\n   to Restart", 10, 100);
+            theme::set_color(Role::Text);
+            text(format!("Holes in one: {}", game.stats.holes_in_one), 10, 82);
+            text(format!("Walls hit: {}", game.stats.walls_hit), 10, 90);
+            if game.daily.is_some() {
+                text("Press Space or X\n  for the menu", 10, 100);
+            } else {
+                text("Press Space or X\n   to Restart", 10, 100);
+            }
+            text("Press Z to watch\n  the replay", 10, 125);
+            let unlocked = Achievement::ALL
+                .iter()
+                .filter(|achievement| game.achievements.has(**achievement))
+                .count();
+            text(
+                format!("Achievements: {}/{}", unlocked, Achievement::ALL.len()),
+                10,
+                145,
+            );
             let gamepad = unsafe { *GAMEPAD1 };
-            if gamepad & BUTTON_1 == 0 && *PREVIOUS_GAMEPAD_X.lock().unwrap() & BUTTON_1 != 0 {
-                game.state = game::State::Playing;
-                game.level = 0;
-                game.score = 0;
-                game.velocity.x = 0.0;
-                game.velocity.y = 0.0;
-                game.initialize_ball();
+            if released(gamepad, BUTTON_1) && game.daily.is_some() {
+                game.state = game::State::Menu;
+                COURSE_MENU.lock().unwrap().open();
+            } else if released(gamepad, BUTTON_1) {
+                game.state = game::State::HoleIntro;
+                game.restart_round();
+            } else if released(gamepad, BUTTON_2) {
+                let level = game.start_level;
+                game.start_playback(level);
             }
         }
-
     }
+    if game.state != previous_state {
+        game.state_changed(previous_state);
+    }
+    game.draw_transition();
     *PREVIOUS_GAMEPAD_X.lock().unwrap() = gamepad;
+    let mut ticks = TICKS.lock().unwrap();
+    *ticks = ticks.wrapping_add(1);
 }
@@ -0,0 +1,96 @@
+use crate::theme::{self, Role};
+use crate::wasm4::*;
+
+const LINE_HEIGHT: i32 = 10;
+
+/// A vertical list of text items picked with the gamepad or the mouse.
+pub struct Menu {
+    pub selected: usize,
+    /// Items shown at once, scrolling to keep the selected one in view.
+    pub rows: usize,
+    /// The first item shown.
+    first: usize,
+    previous_gamepad: u8,
+    previous_mouse: u8,
+}
+
+impl Menu {
+    pub const fn new() -> Self {
+        Self::scrolling(usize::MAX)
+    }
+
+    pub const fn scrolling(rows: usize) -> Self {
+        Self {
+            selected: 0,
+            rows,
+            first: 0,
+            previous_gamepad: 0,
+            previous_mouse: 0,
+        }
+    }
+
+    /// Selects the first item and ignores buttons that are already held down.
+    pub fn open(&mut self) {
+        self.selected = 0;
+        self.first = 0;
+        self.previous_gamepad = unsafe { *GAMEPAD1 };
+        self.previous_mouse = unsafe { *MOUSE_BUTTONS };
+    }
+
+    /// Draws the items with their top left corner at `x`, `y` and returns the
+    /// index of the item chosen this frame.
+    pub fn update(&mut self, items: &[&str], x: i32, y: i32) -> Option<usize> {
+        let gamepad = unsafe { *GAMEPAD1 };
+        let mouse = unsafe { *MOUSE_BUTTONS };
+        let mouse_x = unsafe { *MOUSE_X } as i32;
+        let mouse_y = unsafe { *MOUSE_Y } as i32;
+        let pressed = gamepad & !self.previous_gamepad;
+        let released = self.previous_gamepad & !gamepad;
+        let clicked = self.previous_mouse & !mouse & MOUSE_LEFT != 0;
+        self.previous_gamepad = gamepad;
+        self.previous_mouse = mouse;
+
+        if items.is_empty() {
+            return None;
+        }
+        self.selected = self.selected.min(items.len() - 1);
+
+        if pressed & BUTTON_UP != 0 {
+            self.selected = (self.selected + items.len() - 1) % items.len();
+        } else if pressed & BUTTON_DOWN != 0 {
+            self.selected = (self.selected + 1) % items.len();
+        }
+
+        let rows = self.rows.min(items.len());
+        if self.selected < self.first {
+            self.first = self.selected;
+        } else if self.selected >= self.first + rows {
+            self.first = self.selected + 1 - rows;
+        }
+
+        let hovered = if mouse_x >= x && mouse_y >= y {
+            let row = ((mouse_y - y) / LINE_HEIGHT) as usize;
+            (row < rows).then_some(self.first + row)
+        } else {
+            None
+        };
+
+        for (row, i) in (self.first..self.first + rows).enumerate() {
+            theme::set_color(if i == self.selected { Role::Highlight } else { Role::Text });
+            text(items[i], x + 8, y + row as i32 * LINE_HEIGHT);
+        }
+        theme::set_color(Role::Highlight);
+        text(">", x, y + (self.selected - self.first) as i32 * LINE_HEIGHT);
+
+        if clicked {
+            if let Some(index) = hovered {
+                self.selected = index;
+                return Some(index);
+            }
+        }
+        if released & BUTTON_1 != 0 {
+            return Some(self.selected);
+        }
+        None
+    }
+}
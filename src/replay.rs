@@ -0,0 +1,150 @@
+/// Largest drag (in screen pixels, per axis) a shot can be recorded with.
+pub const MAX_DRAG: i16 = 255;
+/// Largest gap between two shots of a hole that can be encoded.
+const MAX_FRAME_DELTA: u32 = (1 << 14) - 1;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Shot {
+    pub level: u16,
+    /// Frame the shot was taken on, counted from the start of the hole.
+    pub frame: u32,
+    pub x: i16,
+    pub y: i16,
+}
+
+impl Shot {
+    /// Packs the shot into 4 bytes: 14 bits of frames since the previous shot
+    /// followed by 9 bits for each drag axis.
+    fn pack(&self, previous_frame: u32) -> [u8; 4] {
+        let delta = (self.frame - previous_frame).min(MAX_FRAME_DELTA);
+        let x = (self.x as u32) & 0x1ff;
+        let y = (self.y as u32) & 0x1ff;
+        (delta | x << 14 | y << 23).to_le_bytes()
+    }
+
+    fn unpack(level: u16, previous_frame: u32, bytes: [u8; 4]) -> Self {
+        let packed = u32::from_le_bytes(bytes);
+        // Shift the 9 bit fields to the top of an i32 and back to sign extend them.
+        let x = ((packed << 9) as i32 >> 23) as i16;
+        let y = (packed as i32 >> 23) as i16;
+        Shot {
+            level,
+            frame: previous_frame + (packed & MAX_FRAME_DELTA),
+            x,
+            y,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct Replay {
+    pub shots: Vec<Shot>,
+}
+
+impl Replay {
+    pub fn record(&mut self, shot: Shot) {
+        self.shots.push(shot);
+    }
+
+    pub fn clear(&mut self) {
+        self.shots.clear();
+    }
+
+    /// Encodes the replay as a level byte and a shot count per hole, followed by
+    /// the packed shots of that hole.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.shots.len() * 4 + 2);
+        for hole in self.shots.chunk_by(|a, b| a.level == b.level) {
+            for chunk in hole.chunks(u8::MAX as usize) {
+                bytes.push(chunk[0].level as u8);
+                bytes.push(chunk.len() as u8);
+                let mut previous_frame = 0;
+                for shot in chunk {
+                    bytes.extend_from_slice(&shot.pack(previous_frame));
+                    previous_frame = shot.frame;
+                }
+            }
+        }
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        let mut shots = Vec::new();
+        let mut rest = bytes;
+        while let [level, count, tail @ ..] = rest {
+            let count = (*count as usize).min(tail.len() / 4);
+            let mut previous_frame = 0;
+            for packed in tail[..count * 4].chunks_exact(4) {
+                let shot = Shot::unpack(
+                    *level as u16,
+                    previous_frame,
+                    [packed[0], packed[1], packed[2], packed[3]],
+                );
+                previous_frame = shot.frame;
+                shots.push(shot);
+            }
+            rest = &tail[count * 4..];
+        }
+        Replay { shots }
+    }
+
+    pub fn to_hex(&self) -> String {
+        const DIGITS: &[u8; 16] = b"0123456789abcdef";
+        let mut hex = String::new();
+        for byte in self.to_bytes() {
+            hex.push(DIGITS[(byte >> 4) as usize] as char);
+            hex.push(DIGITS[(byte & 0xf) as usize] as char);
+        }
+        hex
+    }
+
+    /// Reads a replay shared as `to_hex` printed it, `None` if it isn't hex.
+    pub fn from_hex(hex: &str) -> Option<Self> {
+        let hex = hex.trim();
+        if !hex.len().is_multiple_of(2) {
+            return None;
+        }
+        let bytes = (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+            .collect::<Option<Vec<u8>>>()?;
+        Some(Self::from_bytes(&bytes))
+    }
+}
+
+/// Feeds the shots of a recorded replay back into the game.
+pub struct Playback {
+    pub replay: Replay,
+    cursor: usize,
+}
+
+impl Playback {
+    pub fn new(replay: Replay) -> Self {
+        Self { replay, cursor: 0 }
+    }
+
+    /// Skips to the first recorded shot of `level`.
+    pub fn seek(&mut self, level: u16) {
+        self.cursor = self
+            .replay
+            .shots
+            .iter()
+            .position(|shot| shot.level >= level)
+            .unwrap_or(self.replay.shots.len());
+    }
+
+    /// Whether every shot has been fed back.
+    pub fn is_finished(&self) -> bool {
+        self.cursor >= self.replay.shots.len()
+    }
+
+    /// Returns the next shot if it is due on this frame of `level`.
+    pub fn next_shot(&mut self, level: u16, frame: u32) -> Option<Shot> {
+        let shot = *self.replay.shots.get(self.cursor)?;
+        if shot.level == level && shot.frame <= frame {
+            self.cursor += 1;
+            return Some(shot);
+        }
+        None
+    }
+}
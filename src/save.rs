@@ -0,0 +1,162 @@
+use crate::levels::{COURSES, HOLE_COUNT};
+use crate::replay::Replay;
+use crate::wasm4::{diskr, diskw};
+
+/// Shots kept of each hole's best attempt. A best of more shots keeps its first
+/// ones and reads as cut short, having fewer shots than `HoleRecord::best`.
+pub const MAX_GHOST_SHOTS: usize = 4;
+
+/// Fields are only ever added at the end, where older and shorter saves read as
+/// zeros, so this changes only if the fields before them have to move.
+const VERSION: u8 = 1;
+/// Best score, then the ghost as a one hole replay (shot count, packed shots),
+/// leaving out the level it starts with as that is the hole's own.
+const HOLE_SIZE: usize = 1 + 1 + MAX_GHOST_SHOTS * 4;
+/// Room for the records of this many holes, so courses can be added without
+/// moving what comes after them.
+const MAX_HOLES: usize = 33;
+const HOLES_SIZE: usize = MAX_HOLES * HOLE_SIZE;
+/// Room for the best rounds of this many courses.
+const MAX_COURSES: usize = 4;
+/// Best round of each course, as a little endian u16.
+const COURSES_SIZE: usize = MAX_COURSES * 2;
+/// Date of the last daily hole played (as `yyyymmdd`) and its score.
+const DAILY_SIZE: usize = 4 + 1;
+/// Room for the editor's level, run length encoded, see `editor::encode`.
+pub const CUSTOM_LEVEL_SIZE: usize = 400;
+/// Length of the custom level as a little endian u16, then the level itself.
+const CUSTOM_SIZE: usize = 2 + CUSTOM_LEVEL_SIZE;
+/// The theme picked in the settings, see `Game::apply_theme`. 0 means the
+/// course's own.
+const SETTINGS_SIZE: usize = 1;
+/// The achievements unlocked, see `achievements::Achievements::unlocked`.
+const ACHIEVEMENTS_SIZE: usize = 1;
+const SAVE_SIZE: usize = 1
+    + HOLES_SIZE
+    + COURSES_SIZE
+    + DAILY_SIZE
+    + CUSTOM_SIZE
+    + SETTINGS_SIZE
+    + ACHIEVEMENTS_SIZE;
+
+const _: () = assert!(HOLE_COUNT <= MAX_HOLES && COURSES.len() <= MAX_COURSES);
+// WASM-4 gives each cart 1024 bytes of disk.
+const _: () = assert!(SAVE_SIZE <= 1024);
+
+#[derive(Debug, Default, Clone)]
+pub struct HoleRecord {
+    /// Fewest strokes the hole was finished in, 0 if it never was.
+    pub best: u8,
+    pub ghost: Replay,
+}
+
+pub struct Save {
+    /// Records of every hole of every course, see `levels::hole_index`.
+    pub holes: Vec<HoleRecord>,
+    /// Fewest strokes each course was finished in, 0 if it never was.
+    pub courses: Vec<u16>,
+    pub daily_date: u32,
+    /// Strokes the daily hole took, 0 if it was left unfinished.
+    pub daily_score: u8,
+    /// The level made in the editor, empty if none was saved.
+    pub custom_level: Vec<u8>,
+    /// 0 for each course's own theme, otherwise one more than the index in
+    /// `theme::THEMES`.
+    pub theme: u8,
+    pub achievements: u8,
+}
+
+impl Save {
+    pub fn load() -> Self {
+        let mut bytes = [0u8; SAVE_SIZE];
+        unsafe {
+            diskr(bytes.as_mut_ptr(), SAVE_SIZE as u32);
+        }
+        Self::from_bytes(&bytes)
+    }
+
+    pub fn store(&self) {
+        let bytes = self.to_bytes();
+        unsafe {
+            diskw(bytes.as_ptr(), bytes.len() as u32);
+        }
+    }
+
+    fn from_bytes(bytes: &[u8; SAVE_SIZE]) -> Self {
+        let mut holes = vec![HoleRecord::default(); HOLE_COUNT];
+        let mut courses = vec![0; COURSES.len()];
+        let mut daily_date = 0;
+        let mut daily_score = 0;
+        let mut custom_level = Vec::new();
+        let mut theme = 0;
+        let mut achievements = 0;
+        if bytes[0] == VERSION {
+            let (hole_bytes, rest) = bytes[1..].split_at(HOLES_SIZE);
+            let (course_bytes, rest) = rest.split_at(COURSES_SIZE);
+            let (daily_bytes, rest) = rest.split_at(DAILY_SIZE);
+            let (custom_bytes, rest) = rest.split_at(CUSTOM_SIZE);
+            let (settings_bytes, achievement_bytes) = rest.split_at(SETTINGS_SIZE);
+            let levels = COURSES.iter().flat_map(|course| 0..course.holes.len() as u8);
+            for ((hole, chunk), level) in holes
+                .iter_mut()
+                .zip(hole_bytes.chunks_exact(HOLE_SIZE))
+                .zip(levels)
+            {
+                hole.best = chunk[0];
+                // The ghost's bytes, with its level put back in front.
+                let mut ghost = [level; HOLE_SIZE];
+                ghost[1..].copy_from_slice(&chunk[1..]);
+                hole.ghost = Replay::from_bytes(&ghost);
+            }
+            for (course, chunk) in courses.iter_mut().zip(course_bytes.chunks_exact(2)) {
+                *course = u16::from_le_bytes([chunk[0], chunk[1]]);
+            }
+            daily_date = u32::from_le_bytes([
+                daily_bytes[0],
+                daily_bytes[1],
+                daily_bytes[2],
+                daily_bytes[3],
+            ]);
+            daily_score = daily_bytes[4];
+            let length = u16::from_le_bytes([custom_bytes[0], custom_bytes[1]]) as usize;
+            custom_level = custom_bytes[2..2 + length.min(CUSTOM_LEVEL_SIZE)].to_vec();
+            theme = settings_bytes[0];
+            achievements = achievement_bytes[0];
+        }
+        Self {
+            holes,
+            courses,
+            daily_date,
+            daily_score,
+            custom_level,
+            theme,
+            achievements,
+        }
+    }
+
+    fn to_bytes(&self) -> [u8; SAVE_SIZE] {
+        let mut bytes = [0u8; SAVE_SIZE];
+        bytes[0] = VERSION;
+        let (hole_bytes, rest) = bytes[1..].split_at_mut(HOLES_SIZE);
+        let (course_bytes, rest) = rest.split_at_mut(COURSES_SIZE);
+        let (daily_bytes, rest) = rest.split_at_mut(DAILY_SIZE);
+        let (custom_bytes, rest) = rest.split_at_mut(CUSTOM_SIZE);
+        let (settings_bytes, achievement_bytes) = rest.split_at_mut(SETTINGS_SIZE);
+        for (hole, chunk) in self.holes.iter().zip(hole_bytes.chunks_exact_mut(HOLE_SIZE)) {
+            chunk[0] = hole.best;
+            if let Some(ghost) = hole.ghost.to_bytes().get(1..) {
+                chunk[1..1 + ghost.len()].copy_from_slice(ghost);
+            }
+        }
+        for (course, chunk) in self.courses.iter().zip(course_bytes.chunks_exact_mut(2)) {
+            chunk.copy_from_slice(&course.to_le_bytes());
+        }
+        daily_bytes[..4].copy_from_slice(&self.daily_date.to_le_bytes());
+        daily_bytes[4] = self.daily_score;
+        custom_bytes[..2].copy_from_slice(&(self.custom_level.len() as u16).to_le_bytes());
+        custom_bytes[2..2 + self.custom_level.len()].copy_from_slice(&self.custom_level);
+        settings_bytes[0] = self.theme;
+        achievement_bytes[0] = self.achievements;
+        bytes
+    }
+}
@@ -0,0 +1,66 @@
+use crate::theme::{self, Role};
+use crate::wasm4::*;
+use crate::OVERVIEW_SCALE;
+
+/// Sprites in the order they sit in the atlases, left to right.
+#[derive(Clone, Copy)]
+pub enum Sprite {
+    /// A vertical wall, rotated for horizontal ones.
+    Wall,
+    /// A top left corner, flipped for the others.
+    Corner,
+    Cup,
+    Ball,
+    Grass,
+}
+
+impl Sprite {
+    /// What pixel values 1, 2 and 3 of the sprite are drawn as.
+    fn roles(&self) -> [Role; 3] {
+        match self {
+            Sprite::Wall | Sprite::Corner => [Role::Wall; 3],
+            Sprite::Cup => [Role::Cup, Role::Cup, Role::Flag],
+            Sprite::Ball => [Role::Ball; 3],
+            Sprite::Grass => [Role::Grass; 3],
+        }
+    }
+}
+
+/// 2bpp sprites at `SCALE`, four pixels square.
+#[rustfmt::skip]
+const ATLAS: [u8; 20] = [
+    0b01000001, 0b00010101, 0b00111010, 0b00111100, 0b00000000,
+    0b01000001, 0b01000000, 0b00111000, 0b11111111, 0b00000000,
+    0b01000001, 0b01000000, 0b00110000, 0b11111111, 0b00000100,
+    0b01000001, 0b01000001, 0b10101000, 0b00111100, 0b00000000,
+];
+const ATLAS_WIDTH: u32 = 20;
+
+/// The same sprites at `OVERVIEW_SCALE`. Grass is left out, at this size it is
+/// only noise.
+#[rustfmt::skip]
+const OVERVIEW_ATLAS: [u8; 4] = [
+    0b01010001, 0b11101111,
+    0b01010101, 0b10101111,
+];
+const OVERVIEW_ATLAS_WIDTH: u32 = 8;
+
+/// Draws `sprite` with its top left corner at `x`, `y`. `scale` is either
+/// `SCALE` or `OVERVIEW_SCALE`; `flags` takes the `BLIT_FLIP_*` and
+/// `BLIT_ROTATE` flags.
+pub fn draw(sprite: Sprite, x: i32, y: i32, scale: u32, flags: u32) {
+    let (atlas, width) = if scale == OVERVIEW_SCALE as u32 {
+        (&OVERVIEW_ATLAS[..], OVERVIEW_ATLAS_WIDTH)
+    } else {
+        (&ATLAS[..], ATLAS_WIDTH)
+    };
+    let src_x = sprite as u32 * scale;
+    if src_x >= width {
+        return;
+    }
+    let [one, two, three] = sprite.roles().map(theme::color);
+    unsafe {
+        *DRAW_COLORS = three << 12 | two << 8 | one << 4;
+    }
+    blit_sub(atlas, x, y, scale, scale, src_x, 0, width, BLIT_2BPP | flags);
+}
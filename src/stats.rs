@@ -0,0 +1,18 @@
+use crate::events::{Event, Observer};
+
+/// Tallies of the round being played, shown when it's over.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Stats {
+    pub walls_hit: u16,
+    pub holes_in_one: u16,
+}
+
+impl Observer for Stats {
+    fn notify(&mut self, event: &Event) {
+        match event {
+            Event::WallHit(_) => self.walls_hit += 1,
+            Event::HoleSunk { strokes: 1, .. } => self.holes_in_one += 1,
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,125 @@
+use std::sync::Mutex;
+
+use crate::wasm4::*;
+
+/// What something on screen is, rather than which palette colour it's drawn in.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Role {
+    Background,
+    Grass,
+    Wall,
+    Cup,
+    /// The flag pole in the cup.
+    Flag,
+    Ball,
+    Text,
+    /// Selected menu items, the HUD and other things to catch the eye.
+    Highlight,
+    Title,
+}
+
+pub struct Theme {
+    pub name: &'static str,
+    pub palette: [u32; 4],
+    /// Palette colour, 1 to 4, of every `Role`, in the order they are declared.
+    roles: [u16; 9],
+}
+
+impl Theme {
+    /// Palette colour, 1 to 4, that `role` is drawn in.
+    pub fn color(&self, role: Role) -> u16 {
+        self.roles[role as usize]
+    }
+
+    /// The colour of `role` as an RGB value.
+    pub fn rgb(&self, role: Role) -> u32 {
+        self.palette[self.color(role) as usize - 1]
+    }
+}
+
+/// Roles of the course themes: the ball shares the brightest colour with titles,
+/// walls and grass the second.
+const COURSE_ROLES: [u16; 9] = [1, 2, 2, 3, 4, 4, 2, 3, 4];
+/// Roles of the accessible themes: the ball and the cup get colours of their
+/// own, and grass is left out so nothing gets lost in it.
+const ACCESSIBLE_ROLES: [u16; 9] = [1, 1, 2, 3, 2, 4, 2, 3, 4];
+
+pub const MEADOW: Theme = Theme {
+    name: "Meadow",
+    palette: [0x1b2f1e, 0x7bc26a, 0xf2d16b, 0xf4f4e8],
+    roles: COURSE_ROLES,
+};
+pub const HARBOUR: Theme = Theme {
+    name: "Harbour",
+    palette: [0x00303b, 0xff7777, 0xffce96, 0xf1f2da],
+    roles: COURSE_ROLES,
+};
+pub const CANYON: Theme = Theme {
+    name: "Canyon",
+    palette: [0x2a1410, 0xb5562c, 0xe8b04a, 0xf7ecd8],
+    roles: COURSE_ROLES,
+};
+/// Theme of the endless mode, the daily hole and the editor's levels.
+pub const DUSK: Theme = Theme {
+    name: "Dusk",
+    palette: [0x2b1b3b, 0x9b7bd8, 0xf2b56b, 0xf4f0f8],
+    roles: COURSE_ROLES,
+};
+/// Black and white walls, with a blue cup and a yellow ball far apart in
+/// brightness.
+pub const HIGH_CONTRAST: Theme = Theme {
+    name: "High contrast",
+    palette: [0x000000, 0xffffff, 0x00a0ff, 0xffe000],
+    roles: ACCESSIBLE_ROLES,
+};
+/// Sky blue and yellow from the Okabe-Ito palette, which stay apart with every
+/// common kind of colour blindness.
+pub const COLOR_BLIND: Theme = Theme {
+    name: "Colour blind",
+    palette: [0x1c1c1c, 0xa0a0a0, 0x56b4e9, 0xf0e442],
+    roles: ACCESSIBLE_ROLES,
+};
+
+/// Every theme that can be picked in the settings. The save keeps an index into
+/// it, so new themes go at the end.
+pub const THEMES: [&Theme; 6] = [
+    &MEADOW,
+    &HARBOUR,
+    &DUSK,
+    &HIGH_CONTRAST,
+    &COLOR_BLIND,
+    &CANYON,
+];
+
+static CURRENT: Mutex<&Theme> = Mutex::new(&MEADOW);
+
+pub fn current() -> &'static Theme {
+    *CURRENT.lock().unwrap()
+}
+
+/// Draws with `theme` from now on and loads its palette.
+pub fn set(theme: &'static Theme) {
+    *CURRENT.lock().unwrap() = theme;
+    unsafe {
+        *PALETTE = theme.palette;
+    }
+}
+
+/// Palette colour, 1 to 4, of `role` in the current theme.
+pub fn color(role: Role) -> u16 {
+    current().color(role)
+}
+
+/// Sets `DRAW_COLORS` to draw text and lines in `role`.
+pub fn set_color(role: Role) {
+    set_draw_colors(Some(role), None);
+}
+
+/// Sets `DRAW_COLORS` to draw shapes filled with `fill` and outlined with
+/// `outline`, or text in `fill` on `outline`. `None` leaves it transparent.
+pub fn set_draw_colors(fill: Option<Role>, outline: Option<Role>) {
+    let nibble = |role: Option<Role>| role.map_or(0, color);
+    unsafe {
+        *DRAW_COLORS = nibble(outline) << 4 | nibble(fill);
+    }
+}
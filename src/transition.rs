@@ -0,0 +1,102 @@
+
+use crate::theme::{self, Role};
+use crate::wasm4::*;
+
+/// Frames a transition takes to cover or uncover the screen.
+const FRAMES: u8 = 20;
+/// Radius of an iris that leaves the whole screen uncovered wherever it's centred.
+const IRIS_RADIUS: f32 = SCREEN_SIZE as f32 * 1.42;
+
+#[derive(Debug, Clone, Copy)]
+pub enum Kind {
+    /// Every colour fades into the background colour.
+    Fade,
+    /// A curtain of the background colour sweeps across from the left.
+    Wipe,
+    /// A circle around `x`, `y` on screen closes or opens.
+    Iris { x: i32, y: i32 },
+}
+
+/// Covers the screen as it changes, or uncovers it afterwards. It is drawn over
+/// everything else at the end of a frame.
+#[derive(Debug, Clone, Copy)]
+pub struct Transition {
+    kind: Kind,
+    closing: bool,
+    frame: u8,
+}
+
+impl Transition {
+    pub fn opening(kind: Kind) -> Self {
+        Self {
+            kind,
+            closing: false,
+            frame: 0,
+        }
+    }
+
+    pub fn closing(kind: Kind) -> Self {
+        Self {
+            kind,
+            closing: true,
+            frame: 0,
+        }
+    }
+
+    pub fn is_closing(&self) -> bool {
+        self.closing
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.frame >= FRAMES
+    }
+
+    /// Draws this frame of the transition, with `palette` being the colours the
+    /// screen has without it, and moves on to the next.
+    pub fn update(&mut self, palette: [u32; 4]) {
+        let progress = self.frame as f32 / FRAMES as f32;
+        let covered = if self.closing { progress } else { 1.0 - progress };
+        self.frame = self.frame.saturating_add(1).min(FRAMES);
+
+        match self.kind {
+            Kind::Fade => unsafe {
+                *PALETTE = palette.map(|color| blend(color, palette[0], covered));
+            },
+            Kind::Wipe => {
+                let width = (covered * SCREEN_SIZE as f32) as u32;
+                theme::set_draw_colors(Some(Role::Background), Some(Role::Background));
+                if self.closing {
+                    rect(0, 0, width, SCREEN_SIZE);
+                } else {
+                    rect((SCREEN_SIZE - width) as i32, 0, width, SCREEN_SIZE);
+                }
+            }
+            Kind::Iris { x, y } => {
+                let radius = (1.0 - covered) * IRIS_RADIUS;
+                theme::set_color(Role::Background);
+                for row in 0..SCREEN_SIZE as i32 {
+                    let dy = (row - y) as f32;
+                    if dy.abs() >= radius {
+                        hline(0, row, SCREEN_SIZE);
+                        continue;
+                    }
+                    let half = (radius * radius - dy * dy).sqrt() as i32;
+                    let left = (x - half).clamp(0, SCREEN_SIZE as i32);
+                    let right = (x + half).clamp(0, SCREEN_SIZE as i32);
+                    hline(0, row, left as u32);
+                    hline(right, row, SCREEN_SIZE - right as u32);
+                }
+            }
+        }
+    }
+}
+
+/// `from` moved `amount` of the way to `to`, channel by channel.
+fn blend(from: u32, to: u32, amount: f32) -> u32 {
+    let channel = |shift: u32| {
+        let from = (from >> shift & 0xff) as f32;
+        let to = (to >> shift & 0xff) as f32;
+        ((from + (to - from) * amount) as u32) << shift
+    };
+    channel(16) | channel(8) | channel(0)
+}
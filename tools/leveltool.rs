@@ -0,0 +1,301 @@
+//! Checks, renders and measures levels outside of the cart.
+//!
+//! Usage: `leveltool <check|ascii|ppm|stats|solve> [FILE...]`. Without files
+//! every hole of every course is used. `ppm` writes one image per level to the
+//! current directory.
+//!
+//! `leveltool replay COURSE HEX` plays back a replay the cart traced at the end
+//! of a round and reports how each hole went.
+
+mod solver;
+
+use std::fs;
+use std::process::ExitCode;
+use std::str::FromStr;
+
+use cart::game::{self, Level, Tile};
+use cart::levels::COURSES;
+use cart::replay::Replay;
+use cart::theme::{self, Role, Theme};
+use cart::SCALE;
+
+/// Strokes the solver gives up after.
+const MAX_STROKES: u32 = 5;
+
+struct Entry {
+    name: String,
+    source: String,
+    theme: &'static Theme,
+    par: Option<u8>,
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let Some(command) = args.first() else {
+        eprintln!("Usage: leveltool <check|ascii|ppm|stats|solve> [FILE...]");
+        eprintln!("       leveltool replay COURSE HEX");
+        return ExitCode::FAILURE;
+    };
+    if command == "replay" {
+        return match replay(&args[1..]) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(error) => {
+                eprintln!("{}", error);
+                ExitCode::FAILURE
+            }
+        };
+    }
+
+    let entries = match entries(&args[1..]) {
+        Ok(entries) => entries,
+        Err(error) => {
+            eprintln!("{}", error);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut failed = false;
+    for entry in &entries {
+        let level = match Level::from_str(&entry.source) {
+            Ok(level) => level,
+            Err(error) => {
+                println!("{}: {}", entry.name, error);
+                failed = true;
+                continue;
+            }
+        };
+        let problems = problems(&level);
+        failed |= !problems.is_empty();
+
+        match command.as_str() {
+            "check" if problems.is_empty() => println!("{}: ok", entry.name),
+            "check" => println!("{}: {}", entry.name, problems.join(", ")),
+            "ascii" => {
+                println!("{}", entry.name);
+                print!("{}", ascii(&level));
+            }
+            "ppm" => {
+                let path = format!("{}.ppm", file_name(&entry.name));
+                if let Err(error) = fs::write(&path, ppm(&level, entry.theme)) {
+                    eprintln!("{}: {}", path, error);
+                    return ExitCode::FAILURE;
+                }
+                println!("{}: wrote {}", entry.name, path);
+            }
+            "stats" => print!("{}", stats(&entry.name, &level)),
+            "solve" => match solver::solve(&level, MAX_STROKES, solver::MAX_BANKS) {
+                Some(shots) => println!(
+                    "{}: {} strokes (par {}), {:.0}% holes in one, shots {:?}",
+                    entry.name,
+                    shots.len(),
+                    entry.par.map_or(String::from("-"), |par| par.to_string()),
+                    solver::hole_in_one_rate(&level) * 100.0,
+                    shots
+                ),
+                None => {
+                    println!("{}: not solved in {} strokes", entry.name, MAX_STROKES);
+                    failed = true;
+                }
+            },
+            _ => {
+                eprintln!("Unknown command: {}", command);
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+
+    if failed {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+fn entries(paths: &[String]) -> Result<Vec<Entry>, String> {
+    if paths.is_empty() {
+        return Ok(COURSES
+            .iter()
+            .flat_map(|course| {
+                course.holes.iter().enumerate().map(|(i, hole)| Entry {
+                    name: format!("{} {} ({})", course.name, i + 1, hole.name),
+                    source: hole.level().to_string(),
+                    theme: course.theme,
+                    par: Some(hole.par),
+                })
+            })
+            .collect());
+    }
+    paths
+        .iter()
+        .map(|path| {
+            Ok(Entry {
+                name: path.clone(),
+                source: fs::read_to_string(path).map_err(|error| format!("{}: {}", path, error))?,
+                // Levels from files are played like the editor's, with this theme.
+                theme: &theme::DUSK,
+                par: None,
+            })
+        })
+        .collect()
+}
+
+/// Plays a traced replay of a course back like the cart does, printing the
+/// strokes of each hole.
+fn replay(args: &[String]) -> Result<(), String> {
+    let [course, hex] = args else {
+        return Err(String::from("Usage: leveltool replay COURSE HEX"));
+    };
+    let course = COURSES
+        .iter()
+        .find(|c| c.name.eq_ignore_ascii_case(course))
+        .ok_or_else(|| format!("No course called {}", course))?;
+    let replay = Replay::from_hex(hex).ok_or("The replay isn't hex")?;
+    let first = replay.shots.first().ok_or("The replay has no shots")?.level;
+    let last = replay.shots.last().map_or(first, |shot| shot.level);
+
+    let mut total = 0;
+    for (level, hole) in course
+        .holes
+        .iter()
+        .enumerate()
+        .take(last as usize + 1)
+        .skip(first as usize)
+    {
+        let map = hole.level();
+        let shots: Vec<_> = replay
+            .shots
+            .iter()
+            .filter(|shot| shot.level == level as u16)
+            .collect();
+        let mut position = map.tee_position().ok_or(format!("{}: no tee", hole.name))?;
+        let mut velocity = game::Position { x: 0.0, y: 0.0 };
+        let mut from = position;
+        let mut taken = 0;
+        let mut frame = 0;
+        // Like `Game::update` followed by `Game::play_recorded_shot`.
+        let sunk = loop {
+            frame += 1;
+            let step = map.step(&mut position, &mut velocity);
+            if step.sunk {
+                break true;
+            }
+            if step.off_course {
+                position = from;
+            }
+            if velocity.x != 0.0 || velocity.y != 0.0 {
+                continue;
+            }
+            match shots.get(taken) {
+                Some(shot) if shot.frame <= frame => {
+                    velocity = game::shot_velocity(shot.x, shot.y);
+                    from = position;
+                    taken += 1;
+                }
+                Some(_) => {}
+                None => break false,
+            }
+        };
+        total += taken;
+        println!(
+            "{} {} ({}): {} strokes (par {}){}",
+            course.name,
+            level + 1,
+            hole.name,
+            taken,
+            hole.par,
+            if sunk { "" } else { ", not sunk" }
+        );
+    }
+    println!("Total: {}", total);
+    Ok(())
+}
+
+fn file_name(name: &str) -> String {
+    let name = name.rsplit('/').next().unwrap_or(name);
+    let name = name.strip_suffix(".txt").unwrap_or(name);
+    name.chars()
+        .filter(|c| c.is_alphanumeric() || *c == ' ' || *c == '-')
+        .map(|c| if c == ' ' { '-' } else { c.to_ascii_lowercase() })
+        .collect()
+}
+
+fn problems(level: &Level) -> Vec<String> {
+    let mut problems = Vec::new();
+    for (tile, name) in [(Tile::Player, "tee"), (Tile::Goal, "cup")] {
+        match level.tiles.iter().filter(|t| **t == tile).count() {
+            1 => {}
+            0 => problems.push(format!("no {}", name)),
+            count => problems.push(format!("{} {}s", count, name)),
+        }
+    }
+    if problems.is_empty() && level.path_length().is_none() {
+        problems.push(String::from("cup can't be reached"));
+    }
+
+    // The ball would roll off the level through a gap in the outer wall.
+    let width = level.width as usize;
+    let rows = level.rows() as usize;
+    let leaks = level.open_area().iter().enumerate().any(|(i, open)| {
+        let (x, y) = (i % width, i / width);
+        *open && (x == 0 || y == 0 || x == width - 1 || y == rows - 1)
+    });
+    if leaks {
+        problems.push(String::from("open to the edge"));
+    }
+    problems
+}
+
+fn ascii(level: &Level) -> String {
+    let open = level.open_area();
+    let mut out = String::new();
+    for (row, open_row) in level
+        .tiles
+        .chunks(level.width as usize)
+        .zip(open.chunks(level.width as usize))
+    {
+        for (tile, open) in row.iter().zip(open_row) {
+            out.push(match tile {
+                Tile::Player => 'o',
+                Tile::Goal => '@',
+                Tile::Empty if *open => '.',
+                Tile::Empty => ' ',
+                _ => '#',
+            });
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// Renders the level like the cart's map, each tile `SCALE` pixels across.
+fn ppm(level: &Level, theme: &Theme) -> Vec<u8> {
+    let scale = SCALE as usize;
+    let width = level.width as usize * scale;
+    let height = level.rows() as usize * scale;
+    let mut out = format!("P6\n{} {}\n255\n", width, height).into_bytes();
+    for y in 0..height {
+        for x in 0..width {
+            let tile = level.tiles[y / scale * level.width as usize + x / scale];
+            let color = theme.rgb(tile.map_role().unwrap_or(Role::Background));
+            out.extend_from_slice(&color.to_be_bytes()[1..]);
+        }
+    }
+    out
+}
+
+fn stats(name: &str, level: &Level) -> String {
+    let mut out = format!("{}\n  size: {}x{}\n", name, level.width, level.rows());
+    for tile in Tile::ALL {
+        let count = level.tiles.iter().filter(|t| **t == tile).count();
+        if count > 0 {
+            out += &format!("  {}: {}\n", tile.name(), count);
+        }
+    }
+    let open = level.open_area().iter().filter(|open| **open).count();
+    out += &format!("  open area: {}\n", open);
+    match level.path_length() {
+        Some(length) => out += &format!("  path length: {}\n", length),
+        None => out += "  path length: -\n",
+    }
+    out
+}
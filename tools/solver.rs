@@ -0,0 +1,138 @@
+//! Finds the fewest strokes that sink a hole, by trying a spread of shots from
+//! the tee and from wherever those shots come to rest.
+
+use std::collections::HashSet;
+use std::f32::consts::TAU;
+
+use cart::game::{shot_velocity, Level, Position};
+
+/// Directions tried for every shot, evenly spread around the ball.
+pub const ANGLES: u32 = 48;
+/// Longest drag, in screen pixels along either axis, a player can make: the
+/// camera keeps the ball near the middle of the 160 pixel screen. Where the
+/// camera stops at the edge of a hole the ball is off centre and a drag can go
+/// further, up to `MAX_DRAG`, with `shot_velocity` only capping the speed past
+/// about 190 pixels. The solver leaves those harder shots out, so it checks
+/// pars against the shots a player can always make.
+const REACH: f32 = 80.0;
+/// Drag lengths tried for every shot, in screen pixels. The ball rolls about a
+/// tile and a third per pixel, so short drags need the finest steps.
+pub const POWERS: [f32; 8] = [3.0, 6.0, 10.0, 16.0, 25.0, 40.0, 60.0, REACH];
+/// Walls a shot may bounce off on its way in for a player to be expected to
+/// make it. Any shot hit hard enough ends up in the cup eventually, so counting
+/// every shot would make nearly every hole a hole in one.
+pub const MAX_BANKS: u32 = 1;
+/// Frames after which a ball that is still rolling is given up on.
+const MAX_FRAMES: u32 = 3000;
+/// Rest positions closer than this, in tiles, count as the same spot.
+const CELL_SIZE: f32 = 0.5;
+
+pub enum Outcome {
+    /// Went in after bouncing off this many walls.
+    Sunk(u32),
+    Rest(Position),
+    /// The ball left the level or never stopped.
+    Lost,
+}
+
+/// Drag, as `Game::shoot` takes it, of `power` pixels at `angle` radians, kept
+/// within the player's reach.
+fn drag(angle: f32, power: f32) -> (i16, i16) {
+    (
+        (libm::cosf(angle) * power).clamp(-REACH, REACH).round() as i16,
+        (libm::sinf(angle) * power).clamp(-REACH, REACH).round() as i16,
+    )
+}
+
+/// Every shot the solver tries, as an angle and a power.
+fn shots() -> Vec<(f32, f32)> {
+    (0..ANGLES)
+        .flat_map(|angle| {
+            let angle = angle as f32 / ANGLES as f32 * TAU;
+            POWERS.iter().map(move |power| (angle, *power))
+        })
+        .collect()
+}
+
+/// Rolls the ball frame by frame, the same way `Game::update` does.
+pub fn roll(level: &Level, mut position: Position, shot: (i16, i16)) -> Outcome {
+    let mut velocity = shot_velocity(shot.0, shot.1);
+    let mut banks = 0;
+    for _ in 0..MAX_FRAMES {
+        let step = level.step(&mut position, &mut velocity);
+        if step.sunk {
+            return Outcome::Sunk(banks);
+        }
+        if step.off_course {
+            return Outcome::Lost;
+        }
+        if step.impact.is_some() {
+            banks += 1;
+        }
+        if velocity.x == 0.0 && velocity.y == 0.0 {
+            return Outcome::Rest(position);
+        }
+    }
+    Outcome::Lost
+}
+
+/// Share of the solver's shots from the tee that go in, however many walls
+/// they bounce off.
+pub fn hole_in_one_rate(level: &Level) -> f32 {
+    let Some(tee) = level.tee_position() else {
+        return 0.0;
+    };
+    let shots = shots();
+    let sunk = shots
+        .iter()
+        .filter(|(angle, power)| matches!(roll(level, tee, drag(*angle, *power)), Outcome::Sunk(_)))
+        .count();
+    sunk as f32 / shots.len() as f32
+}
+
+/// The shortest sequence of drags that sinks the hole in at most `max_strokes`,
+/// or `None` if none of the tried shots manage it. The last shot may bounce off
+/// at most `max_banks` walls, so the length is an estimate of par rather than of
+/// the luckiest shot.
+pub fn solve(level: &Level, max_strokes: u32, max_banks: u32) -> Option<Vec<(i16, i16)>> {
+    let shots = shots();
+    // Every spot reached so far, with the spot and the shot it was reached from.
+    let mut nodes: Vec<(Position, usize, (i16, i16))> =
+        vec![(level.tee_position()?, usize::MAX, (0, 0))];
+    let mut seen = HashSet::new();
+    let mut frontier = 0..1;
+
+    for _ in 0..max_strokes {
+        let end = nodes.len();
+        for node in frontier.clone() {
+            for &(angle, power) in &shots {
+                let shot = drag(angle, power);
+                match roll(level, nodes[node].0, shot) {
+                    Outcome::Sunk(banks) if banks > max_banks => {}
+                    Outcome::Sunk(_) => {
+                        let mut sequence = vec![shot];
+                        let mut node = node;
+                        while nodes[node].1 != usize::MAX {
+                            sequence.push(nodes[node].2);
+                            node = nodes[node].1;
+                        }
+                        sequence.reverse();
+                        return Some(sequence);
+                    }
+                    Outcome::Rest(position) => {
+                        let cell = (
+                            (position.x / CELL_SIZE) as i32,
+                            (position.y / CELL_SIZE) as i32,
+                        );
+                        if seen.insert(cell) {
+                            nodes.push((position, node, shot));
+                        }
+                    }
+                    Outcome::Lost => {}
+                }
+            }
+        }
+        frontier = end..nodes.len();
+    }
+    None
+}
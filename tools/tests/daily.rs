@@ -0,0 +1,25 @@
+//! Checks that each daily hole gets a single attempt.
+
+use cart::daily::{is_playable, Date};
+
+const DATE: Date = Date {
+    year: 2024,
+    month: 3,
+    day: 15,
+};
+
+#[test]
+fn each_day_is_played_once() {
+    assert!(is_playable(DATE, 0), "never played");
+    let last_played = DATE.to_u32();
+    assert!(!is_playable(DATE, last_played), "same day again");
+    // Going back to an earlier day would lose the later day's result.
+    let earlier = Date { day: 14, ..DATE };
+    assert!(!is_playable(earlier, last_played), "earlier day");
+    let later = Date {
+        year: 2024,
+        month: 4,
+        day: 1,
+    };
+    assert!(is_playable(later, last_played), "later day");
+}
@@ -0,0 +1,151 @@
+//! Checks the events play queues and what the observers make of them.
+
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use cart::achievements::{Achievement, Achievements};
+use cart::audio::Audio;
+use cart::effects::Effects;
+use cart::events::{Event, Observer};
+use cart::game::{shot_velocity, Impact, Level, Position};
+use cart::levels::COURSES;
+use cart::stats::Stats;
+
+/// Sounds played, counted by the stand in for WASM-4's `tone`.
+static TONES: AtomicU32 = AtomicU32::new(0);
+
+#[no_mangle]
+extern "C" fn tone(_frequency: u32, _duration: u32, _volume: u32, _flags: u32) {
+    TONES.fetch_add(1, Ordering::Relaxed);
+}
+
+const ORIGIN: Position = Position { x: 0.0, y: 0.0 };
+
+fn sunk(strokes: u16) -> Event {
+    Event::HoleSunk {
+        strokes,
+        ball: ORIGIN,
+        cup: ORIGIN,
+    }
+}
+
+#[test]
+fn stats_count_each_event() {
+    let mut stats = Stats::default();
+    let events = [
+        Event::LevelChanged(0),
+        Event::ShotTaken { x: 10, y: -4 },
+        Event::WallHit(Impact {
+            position: ORIGIN,
+            speed: 0.2,
+        }),
+        Event::BallStopped(ORIGIN),
+        Event::ShotTaken { x: 3, y: 3 },
+        sunk(2),
+        Event::LevelChanged(1),
+        Event::ShotTaken { x: 0, y: 12 },
+        sunk(1),
+    ];
+    for event in &events {
+        stats.notify(event);
+    }
+    assert_eq!(
+        stats,
+        Stats {
+            walls_hit: 1,
+            holes_in_one: 1,
+        }
+    );
+}
+
+#[test]
+fn a_hole_queues_its_events() {
+    let hole = COURSES[0]
+        .holes
+        .iter()
+        .find(|hole| hole.name == "The Post")
+        .unwrap();
+    let level = hole.level();
+    // Off the wall to the right of the tee, then round the post into the cup.
+    let shots = [(6, 0), (-8, 14)];
+
+    let mut events = Vec::new();
+    let mut position = level.tee_position().unwrap();
+    for (strokes, (x, y)) in (1..).zip(shots) {
+        // As `Game::shoot` and `Game::update` do.
+        events.push(Event::ShotTaken { x, y });
+        let mut velocity = shot_velocity(x, y);
+        loop {
+            let step = level.roll(&mut position, &mut velocity, strokes, &mut events);
+            if step.sunk || (velocity.x == 0.0 && velocity.y == 0.0) {
+                break;
+            }
+        }
+    }
+    assert!(
+        matches!(
+            events[..],
+            [
+                Event::ShotTaken { x: 6, y: 0 },
+                Event::WallHit(_),
+                Event::BallStopped(_),
+                Event::ShotTaken { x: -8, y: 14 },
+                Event::WallHit(_),
+                Event::HoleSunk { strokes: 2, .. },
+            ]
+        ),
+        "{:?}",
+        events
+    );
+
+    let mut audio = Audio;
+    let mut effects = Effects::new();
+    let mut achievements = Achievements::default();
+    for event in &events {
+        audio.notify(event);
+        effects.notify(event);
+        achievements.notify(event);
+    }
+    assert_eq!(TONES.load(Ordering::Relaxed), 4);
+    assert!(effects.sinking(), "the ball drops into the cup");
+    assert!(achievements.has(Achievement::BankShot));
+    assert!(achievements.is_new(Achievement::BankShot));
+    assert!(!achievements.has(Achievement::HoleInOne));
+    assert!(!achievements.has(Achievement::TrickShot));
+
+    // Playing the hole again unlocks nothing new.
+    achievements.notify(&Event::LevelChanged(0));
+    for event in &events {
+        achievements.notify(event);
+    }
+    assert!(achievements.has(Achievement::BankShot));
+    assert!(!achievements.is_new(Achievement::BankShot));
+}
+
+#[test]
+fn a_ball_off_the_course_enters_a_hazard() {
+    // The right wall has a gap the ball can roll out through.
+    let level = Level::from_str("\nl---r\n|p   \nL---R").unwrap();
+    let tee = level.tee_position().unwrap();
+    assert!(level.contains(tee));
+    assert!(!level.contains(Position { x: 5.0, y: tee.y }));
+    assert!(!level.contains(Position { x: tee.x, y: 0.5 }));
+
+    let mut events = Vec::new();
+    let mut position = tee;
+    let mut velocity = shot_velocity(-60, 0);
+    let step = loop {
+        let step = level.roll(&mut position, &mut velocity, 1, &mut events);
+        if step.off_course || (velocity.x == 0.0 && velocity.y == 0.0) {
+            break step;
+        }
+    };
+    assert!(step.off_course, "the ball leaves through the gap");
+    assert!(!level.contains(position));
+    assert_eq!((velocity.x, velocity.y), (0.0, 0.0));
+    assert!(
+        matches!(events[..], [Event::HazardEntered(_)]),
+        "{:?}",
+        events
+    );
+}
@@ -0,0 +1,58 @@
+//! Checks every course hole with the solver, so a level change that breaks a
+//! hole or makes it too easy is caught. The solver drags no further than its
+//! `REACH`, so a hole can still be easier for a player who hits it harder from
+//! near its edge.
+
+#[path = "../solver.rs"]
+mod solver;
+
+use cart::levels::COURSES;
+
+/// Share of the solver's tee shots that may go in before a hole counts as a
+/// free hole in one.
+const MAX_HOLE_IN_ONE_RATE: f32 = 0.25;
+
+#[test]
+fn holes_can_be_made_in_par() {
+    let mut failures = Vec::new();
+    for course in &COURSES {
+        for hole in course.holes {
+            let level = hole.level();
+            match solver::solve(&level, hole.par as u32, solver::MAX_BANKS) {
+                Some(shots) => println!("{} {}: {:?}", course.name, hole.name, shots),
+                None => failures.push(format!("{} {}", course.name, hole.name)),
+            }
+        }
+    }
+    assert!(failures.is_empty(), "not solved in par: {:?}", failures);
+}
+
+#[test]
+fn holes_are_not_under_par() {
+    let mut failures = Vec::new();
+    for course in &COURSES {
+        for hole in course.holes {
+            let level = hole.level();
+            // More than one under par means the par is set too high.
+            let least = hole.par.saturating_sub(2) as u32;
+            if let Some(shots) = solver::solve(&level, least, solver::MAX_BANKS) {
+                failures.push(format!("{} {} ({} strokes)", course.name, hole.name, shots.len()));
+            }
+        }
+    }
+    assert!(failures.is_empty(), "more than one under par: {:?}", failures);
+}
+
+#[test]
+fn holes_are_not_free_holes_in_one() {
+    let mut failures = Vec::new();
+    for course in &COURSES {
+        for hole in course.holes {
+            let rate = solver::hole_in_one_rate(&hole.level());
+            if rate > MAX_HOLE_IN_ONE_RATE {
+                failures.push(format!("{} {} ({:.0}%)", course.name, hole.name, rate * 100.0));
+            }
+        }
+    }
+    assert!(failures.is_empty(), "too easy: {:?}", failures);
+}
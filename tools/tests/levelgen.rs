@@ -0,0 +1,56 @@
+//! Checks the holes built for endless and the daily hole over a spread of seeds.
+
+use cart::game::{Level, Tile};
+use cart::levelgen::{generate, MAX_DIFFICULTY};
+
+const SEEDS: u32 = 200;
+
+/// What's wrong with a generated hole, empty if nothing is.
+fn problems(level: &Level) -> Vec<String> {
+    let mut problems = Vec::new();
+    for (tile, name) in [(Tile::Player, "tee"), (Tile::Goal, "cup")] {
+        let count = level.tiles.iter().filter(|t| **t == tile).count();
+        if count != 1 {
+            problems.push(format!("{} {}s", count, name));
+        }
+    }
+    let width = level.width as usize;
+    let rows = level.rows() as usize;
+    let leaks = level.open_area().iter().enumerate().any(|(i, open)| {
+        let (x, y) = (i % width, i / width);
+        *open && (x == 0 || y == 0 || x == width - 1 || y == rows - 1)
+    });
+    if leaks {
+        problems.push(String::from("open to the edge"));
+    }
+    if level.path_length().is_none() {
+        problems.push(String::from("cup can't be reached"));
+    }
+    problems
+}
+
+#[test]
+fn generated_holes_are_playable() {
+    let mut failures = Vec::new();
+    for difficulty in 0..=MAX_DIFFICULTY {
+        for seed in 0..SEEDS {
+            let level = generate(seed, difficulty);
+            let problems = problems(&level);
+            if !problems.is_empty() {
+                failures.push(format!("seed {} difficulty {}: {:?}", seed, difficulty, problems));
+            }
+        }
+    }
+    assert!(failures.is_empty(), "{:#?}", failures);
+}
+
+#[test]
+fn a_seed_always_builds_the_same_hole() {
+    for difficulty in 0..=MAX_DIFFICULTY {
+        for seed in 0..SEEDS {
+            let (first, second) = (generate(seed, difficulty), generate(seed, difficulty));
+            assert_eq!(first.width, second.width, "seed {} difficulty {}", seed, difficulty);
+            assert!(first.tiles == second.tiles, "seed {} difficulty {}", seed, difficulty);
+        }
+    }
+}
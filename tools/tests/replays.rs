@@ -0,0 +1,15 @@
+//! Checks that replays survive being shared as hex.
+
+use cart::replay::{Replay, Shot};
+
+#[test]
+fn replays_round_trip_through_hex() {
+    let mut replay = Replay::default();
+    for (level, frame, x, y) in [(0, 1, 40, -12), (0, 300, -255, 255), (2, 5, 0, 3)] {
+        replay.record(Shot { level, frame, x, y });
+    }
+    let shared = Replay::from_hex(&replay.to_hex()).unwrap();
+    assert_eq!(shared.shots, replay.shots);
+    assert!(Replay::from_hex("0g").is_none());
+    assert!(Replay::from_hex("abc").is_none());
+}
@@ -0,0 +1,102 @@
+//! Fires a fixed set of shots on every course hole and compares where the ball
+//! goes with the files in `tests/golden`, so changes to the physics show up.
+//!
+//! Run with `UPDATE_GOLDEN=1` to write the files again after a deliberate change.
+
+use std::fmt::Write;
+use std::fs;
+use std::path::PathBuf;
+
+use cart::game::{shot_velocity, Level};
+use cart::levels::COURSES;
+
+/// Drags fired from the tee: eight directions at a soft, a medium and a full
+/// strength shot.
+const DIRECTIONS: [(i16, i16); 8] = [
+    (1, 0),
+    (1, 1),
+    (0, 1),
+    (-1, 1),
+    (-1, 0),
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+];
+const POWERS: [i16; 3] = [8, 40, 200];
+/// Frames between the positions written down.
+const SAMPLE_RATE: u32 = 30;
+const MAX_FRAMES: u32 = 3000;
+
+fn trajectories(level: &Level) -> String {
+    let tee = level.tee_position().unwrap();
+    let mut out = String::new();
+    for (x, y) in DIRECTIONS {
+        for power in POWERS {
+            let shot = (x * power, y * power);
+            writeln!(out, "shot {} {}", shot.0, shot.1).unwrap();
+
+            let mut position = tee;
+            let mut velocity = shot_velocity(shot.0, shot.1);
+            let mut frame = 0;
+            let end = loop {
+                let step = level.step(&mut position, &mut velocity);
+                frame += 1;
+                if step.sunk {
+                    break "sunk";
+                }
+                if step.off_course {
+                    break "off course";
+                }
+                if velocity.x == 0.0 && velocity.y == 0.0 {
+                    break "rest";
+                }
+                if frame == MAX_FRAMES {
+                    break "rolling";
+                }
+                if frame % SAMPLE_RATE == 0 {
+                    writeln!(out, "{:4} {:.4} {:.4}", frame, position.x, position.y).unwrap();
+                }
+            };
+            writeln!(out, "{} {} {:.4} {:.4}", end, frame, position.x, position.y).unwrap();
+        }
+    }
+    out
+}
+
+#[test]
+fn trajectories_match_golden_files() {
+    let update = std::env::var_os("UPDATE_GOLDEN").is_some();
+    let directory = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/golden");
+    let mut failures = Vec::new();
+
+    for course in &COURSES {
+        for (i, hole) in course.holes.iter().enumerate() {
+            let name = format!("{}-{}", course.name.to_lowercase(), i + 1);
+            let path = directory.join(format!("{}.txt", name));
+            let actual = trajectories(&hole.level());
+
+            if update {
+                fs::create_dir_all(&directory).unwrap();
+                fs::write(&path, actual).unwrap();
+                continue;
+            }
+            let expected = fs::read_to_string(&path).unwrap_or_default();
+            let mismatch = expected
+                .lines()
+                .zip(actual.lines())
+                .position(|(expected, actual)| expected != actual);
+            match mismatch {
+                Some(line) => failures.push(format!("{} differs from line {}", name, line + 1)),
+                None if expected.lines().count() != actual.lines().count() => {
+                    failures.push(format!("{} has a different length", name))
+                }
+                None => {}
+            }
+        }
+    }
+    assert!(
+        failures.is_empty(),
+        "trajectories changed, run with UPDATE_GOLDEN=1 if that's intended: {:?}",
+        failures
+    );
+}